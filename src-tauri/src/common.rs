@@ -2,22 +2,41 @@ use std::collections::{HashMap, HashSet};
 
 use derive_new::new;
 use ndarray::{s, Array1, Array2, Axis};
-use petgraph::graph::DiGraph;
-use serde::Serialize;
+use petgraph::graph::{DiGraph, NodeIndex};
+use serde::{Deserialize, Serialize};
 
-pub(crate) fn fire_transition(state: &State, effect_matrix: &PTMatrix, t: usize) -> State {
-    state + &effect_matrix.slice(s![t, ..])
+use crate::expression::check_place_name;
+
+pub(crate) fn fire_transition(state: &State, net: &Net, t: usize) -> State {
+    if net.t_reset.is_none() {
+        return state + &net.t_effect.slice(s![t, ..]);
+    }
+    produce_tokens(&consume_tokens(state, net, t), net, t)
+}
+
+/// Removes the input tokens of the transition and empties all places connected by reset arcs.
+pub(crate) fn consume_tokens(state: &State, net: &Net, t: usize) -> State {
+    let mut new_state = state - &net.t_in.slice(s![t, ..]);
+    if let Some(t_reset) = &net.t_reset {
+        new_state.zip_mut_with(&t_reset.row(t), |tokens, &reset| {
+            if reset > 0 {
+                *tokens = 0
+            }
+        });
+    }
+    new_state
 }
 
-pub(crate) fn find_active_transitions(marking: &State, transition_inputs: &PTMatrix) -> InputState {
+pub(crate) fn produce_tokens(state: &State, net: &Net, t: usize) -> State {
+    state + &net.t_out.slice(s![t, ..])
+}
+
+pub(crate) fn find_active_transitions(marking: &State, net: &Net) -> InputState {
     let mut active_transitions = Vec::new();
 
-    // Compare each row of the matrix to the reference array
-    for (row_index, row) in transition_inputs.axis_iter(Axis(0)).enumerate() {
-        // Check whether the marking is at least as large as the edge weight.
-        if marking.iter().zip(row.iter()).all(|(&a, &b)| a >= b) {
-            // If the marking is large enough, the transition is active.
-            active_transitions.push(row_index as i16);
+    for t in 0..net.transition_count() {
+        if net.is_enabled(marking, t) {
+            active_transitions.push(t as i16);
         }
     }
 
@@ -26,36 +45,34 @@ pub(crate) fn find_active_transitions(marking: &State, transition_inputs: &PTMat
 
 pub(crate) fn find_active_transitions_from_firing_set(
     marking: &State,
-    transition_inputs: &PTMatrix,
+    net: &Net,
     mut last_step_active: InputState,
     firing_updates: &FiringUpdates,
     last_fired: &usize,
 ) -> InputState {
     if (last_step_active.len()) == 0 {
-        return find_active_transitions(marking, transition_inputs);
+        return find_active_transitions(marking, net);
     }
 
-    // Compare each row of the matrix to the reference array
     let might_be_disabled = firing_updates.might_disable.get(last_fired).unwrap();
     let can_be_enabled = firing_updates.can_enable.get(last_fired).unwrap();
-    for (row_index, row) in transition_inputs.axis_iter(Axis(0)).enumerate() {
-        // Check whether the marking is at least as large as the edge weight.
-        let row_index_i16 = &(row_index as i16);
-        let was_active = last_step_active.contains(row_index_i16);
-        let remove_if_disabled = was_active && might_be_disabled.contains(row_index_i16);
-        let add_if_enabled = !was_active && can_be_enabled.contains(row_index_i16);
+    for t in 0..net.transition_count() {
+        let t_i16 = &(t as i16);
+        let was_active = last_step_active.contains(t_i16);
+        let remove_if_disabled = was_active && might_be_disabled.contains(t_i16);
+        let add_if_enabled = !was_active && can_be_enabled.contains(t_i16);
 
         if add_if_enabled || remove_if_disabled {
             // check if it is enabled
-            let enabled = marking.iter().zip(row.iter()).all(|(&a, &b)| a >= b);
+            let enabled = net.is_enabled(marking, t);
 
             // is newly enabled
             if enabled && add_if_enabled {
-                last_step_active.push(*row_index_i16)
+                last_step_active.push(*t_i16)
             }
             // was enabled but is now disabled
             else if !enabled && remove_if_disabled {
-                if let Some(index) = last_step_active.iter().position(|x| x == row_index_i16) {
+                if let Some(index) = last_step_active.iter().position(|x| x == t_i16) {
                     last_step_active.remove(index);
                 }
             }
@@ -65,26 +82,23 @@ pub(crate) fn find_active_transitions_from_firing_set(
     return last_step_active;
 }
 
-pub(crate) fn create_firing_updates(t_in: &PTMatrix, t_out: &PTMatrix) -> FiringUpdates {
-    let places = t_in.place_count();
-    let transitions = t_in.transition_count();
-    let mut adds_tokens_to: HashMap<usize, InputState> = HashMap::new(); // transition add to places
-    let mut has_tokens_removed_from: HashMap<usize, InputState> = HashMap::new(); // place have tokens removed by transitions
-
-    for p in 0..places {
-        has_tokens_removed_from.insert(p, Vec::new());
-    }
+pub(crate) fn create_firing_updates(net: &Net) -> FiringUpdates {
+    let places = net.place_count();
+    let transitions = net.transition_count();
+    // transitions that need tokens in 'p', because they consume or read them
+    let mut requires_tokens_from: Vec<Vec<i16>> = vec![Vec::new(); places];
+    // transitions that need 'p' to hold few tokens, because of an inhibitor arc or because they would exceed its capacity
+    let mut limited_by: Vec<Vec<i16>> = vec![Vec::new(); places];
 
     for t in 0..transitions {
-        let transition_consumes = t_in.slice(s![t, ..]);
-        let transition_creates = t_out.slice(s![t, ..]);
-        adds_tokens_to.insert(t, Vec::new());
-        for p in 0..transition_creates.len_of(Axis(0)) {
-            if transition_creates[[p]] > 0 {
-                adds_tokens_to.get_mut(&t).unwrap().push(p as i16);
+        for p in 0..places {
+            if net.t_in[[t, p]] > 0 || net.reads(t, p) {
+                requires_tokens_from[p].push(t as i16);
             }
-            if transition_consumes[[p]] > 0 {
-                has_tokens_removed_from.get_mut(&p).unwrap().push(t as i16);
+            let exceeds_capacity =
+                net.capacity(p).is_some() && net.t_effect[[t, p]] > 0 && !net.resets(t, p);
+            if net.inhibits(t, p) || exceeds_capacity {
+                limited_by[p].push(t as i16);
             }
         }
     }
@@ -93,27 +107,27 @@ pub(crate) fn create_firing_updates(t_in: &PTMatrix, t_out: &PTMatrix) -> Firing
     let mut might_disable: HashMap<usize, HashSet<i16>> = HashMap::new();
     for t in 0..transitions {
         let mut enables: HashSet<i16> = HashSet::new();
-        // every transition that adds a token to 'p' might activate all transitions that consume from 'p'
-        let adds_to_places = adds_tokens_to.get(&t).unwrap();
-        adds_to_places.iter().for_each(|&p| {
-            let all_places = has_tokens_removed_from.get(&(p as usize)).unwrap();
-            all_places.iter().for_each(|p| {
-                enables.insert(p.clone());
-            });
-        });
-        can_enable.insert(t, enables);
-
         let mut disables: HashSet<i16> = HashSet::new();
-        // every transition that consumes a token from 'p' might disable other transitions that consume from 'p'
-        has_tokens_removed_from
-            .values()
-            .for_each(|consuming_transitions| {
-                if consuming_transitions.contains(&(t as i16)) {
-                    consuming_transitions.iter().for_each(|other_transition| {
-                        disables.insert(other_transition.clone());
-                    })
-                }
-            });
+        for p in 0..places {
+            // a reset arc might both add or remove tokens, depending on the marking
+            let resets = net.resets(t, p);
+            // every transition that adds a token to 'p' might activate all transitions that need tokens from 'p'
+            if net.t_out[[t, p]] > 0 || resets {
+                enables.extend(&requires_tokens_from[p]);
+            }
+            // every transition that consumes a token from 'p' might disable other transitions that need tokens from 'p'
+            if net.t_in[[t, p]] > 0 || resets {
+                disables.extend(&requires_tokens_from[p]);
+            }
+            // inhibitor arcs and capacities work the other way around
+            if net.t_effect[[t, p]] < 0 || resets {
+                enables.extend(&limited_by[p]);
+            }
+            if net.t_effect[[t, p]] > 0 || resets {
+                disables.extend(&limited_by[p]);
+            }
+        }
+        can_enable.insert(t, enables);
         might_disable.insert(t, disables);
     }
 
@@ -138,9 +152,288 @@ pub(crate) fn input_matrix_to_matrix(input: &InputMatrix) -> PTMatrix {
 
 pub type InputState = Vec<i16>;
 pub type InputMatrix = Vec<InputState>;
+/// The maximum number of tokens per place, where `None` means that the place is unlimited
+pub type InputCapacities = Vec<Option<i16>>;
 pub type State = Array1<i16>;
 pub type PTMatrix = Array2<i16>;
 pub type ReachabilityGraph = DiGraph<State, i16>;
+pub type SessionId = u32;
+
+/// Marks a place of a coverability graph node that can hold arbitrarily many tokens
+pub(crate) const OMEGA: i16 = i16::MAX;
+
+/// The arcs of a net, converted to matrices with one row per transition and one column per place
+pub(crate) struct Net {
+    pub(crate) t_in: PTMatrix,
+    pub(crate) t_out: PTMatrix,
+    pub(crate) t_effect: PTMatrix,
+    /// Inhibitor arcs. A transition is only enabled while the place holds fewer tokens than the arc weight.
+    /// A weight of 0 means that there is no inhibitor arc.
+    pub(crate) t_inhibit: Option<PTMatrix>,
+    /// Reset arcs. Firing the transition empties the place. Any weight above 0 is a reset arc.
+    pub(crate) t_reset: Option<PTMatrix>,
+    /// Read arcs. A transition is only enabled if the place holds at least as many tokens as the arc weight,
+    /// but firing it does not consume them. A weight of 0 means that there is no read arc.
+    pub(crate) t_read: Option<PTMatrix>,
+    /// Place capacities. A transition is only enabled if firing it does not exceed the capacity of any place.
+    pub(crate) capacities: Option<InputCapacities>,
+    /// Static transition priorities. Only the enabled transitions with the highest priority may fire.
+    pub(crate) priorities: Option<InputState>,
+    /// The names of the places, which expressions may use instead of `p3`. They must not be keywords of the formulas
+    /// or look like `p3` or `t4`.
+    pub(crate) place_names: Option<Vec<String>>,
+}
+
+impl Net {
+    pub(crate) fn new(
+        transition_inputs: &InputMatrix,
+        transition_outputs: &InputMatrix,
+    ) -> Result<Self, String> {
+        let places = transition_inputs.place_count();
+        if transition_inputs.iter().any(|row| row.len() != places) {
+            return Err("❌The input matrix is not rectangular!".to_string());
+        }
+        let t_in: PTMatrix = input_matrix_to_matrix(transition_inputs);
+        check_dimensions(&t_in, transition_outputs, "output")?;
+        let t_out: PTMatrix = input_matrix_to_matrix(transition_outputs);
+        let t_effect: PTMatrix = &t_out - &t_in;
+
+        Ok(Net {
+            t_in,
+            t_out,
+            t_effect,
+            t_inhibit: None,
+            t_reset: None,
+            t_read: None,
+            capacities: None,
+            priorities: None,
+            place_names: None,
+        })
+    }
+
+    pub(crate) fn with_inhibitors(
+        mut self,
+        transition_inhibitors: Option<InputMatrix>,
+    ) -> Result<Self, String> {
+        self.t_inhibit = self.convert_optional(transition_inhibitors, "inhibitor")?;
+        Ok(self)
+    }
+
+    pub(crate) fn with_resets(
+        mut self,
+        transition_resets: Option<InputMatrix>,
+    ) -> Result<Self, String> {
+        self.t_reset = self.convert_optional(transition_resets, "reset")?;
+        Ok(self)
+    }
+
+    pub(crate) fn with_reads(
+        mut self,
+        transition_reads: Option<InputMatrix>,
+    ) -> Result<Self, String> {
+        self.t_read = self.convert_optional(transition_reads, "read")?;
+        Ok(self)
+    }
+
+    pub(crate) fn with_capacities(
+        mut self,
+        capacities: Option<InputCapacities>,
+    ) -> Result<Self, String> {
+        if let Some(capacities) = &capacities {
+            if capacities.len() != self.place_count() {
+                return Err("❌The capacities do not match the places of the net!".to_string());
+            }
+            if capacities.iter().flatten().any(|&capacity| capacity < 0) {
+                return Err("❌Capacities must not be negative!".to_string());
+            }
+        }
+        self.capacities = capacities;
+        Ok(self)
+    }
+
+    pub(crate) fn with_priorities(
+        mut self,
+        priorities: Option<InputState>,
+    ) -> Result<Self, String> {
+        if priorities
+            .as_ref()
+            .is_some_and(|priorities| priorities.len() != self.transition_count())
+        {
+            return Err("❌The priorities do not match the transitions of the net!".to_string());
+        }
+        self.priorities = priorities;
+        Ok(self)
+    }
+
+    pub(crate) fn with_place_names(mut self, names: Option<Vec<String>>) -> Result<Self, String> {
+        if names
+            .as_ref()
+            .is_some_and(|names| names.len() != self.place_count())
+        {
+            return Err("❌The place names do not match the places of the net!".to_string());
+        }
+        for (p, name) in names.iter().flatten().enumerate() {
+            check_place_name(p, name)?;
+            if let Some(other) = names
+                .iter()
+                .flatten()
+                .take(p)
+                .position(|other| other == name)
+            {
+                return Err(format!(
+                    "❌Place {} can not be named '{}', because place {} already has that name!",
+                    p, name, other
+                ));
+            }
+        }
+        self.place_names = names;
+        Ok(self)
+    }
+
+    /// Checks that the marking matches the places of the net and respects their capacities.
+    pub(crate) fn check_marking(&self, marking: &InputState) -> Result<(), String> {
+        if marking.len() != self.place_count() && self.transition_count() > 0 {
+            return Err("❌The marking does not match the places of the net!".to_string());
+        }
+        if let Some(capacities) = &self.capacities {
+            let exceeded = marking
+                .iter()
+                .zip(capacities.iter())
+                .position(|(&tokens, capacity)| capacity.is_some_and(|c| tokens > c));
+            if let Some(p) = exceeded {
+                return Err(format!(
+                    "❌The marking exceeds the capacity of place {}!",
+                    p
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn convert_optional(
+        &self,
+        input: Option<InputMatrix>,
+        kind: &str,
+    ) -> Result<Option<PTMatrix>, String> {
+        return match input {
+            Some(input) => {
+                check_dimensions(&self.t_in, &input, kind)?;
+                Ok(Some(input_matrix_to_matrix(&input)))
+            }
+            None => Ok(None),
+        };
+    }
+
+    pub(crate) fn is_enabled(&self, marking: &State, t: usize) -> bool {
+        // Check whether the marking is at least as large as the edge weight.
+        let has_tokens = marking
+            .iter()
+            .zip(self.t_in.row(t).iter())
+            .all(|(&a, &b)| a >= b);
+
+        has_tokens
+            && self.t_read.as_ref().is_none_or(|t_read| {
+                marking
+                    .iter()
+                    .zip(t_read.row(t).iter())
+                    .all(|(&a, &b)| a >= b)
+            })
+            && self.t_inhibit.as_ref().is_none_or(|t_inhibit| {
+                marking
+                    .iter()
+                    .zip(t_inhibit.row(t).iter())
+                    .all(|(&a, &b)| b == 0 || a < b)
+            })
+            && self.capacities.as_ref().is_none_or(|capacities| {
+                fire_transition(marking, self, t)
+                    .iter()
+                    .zip(capacities.iter())
+                    .all(|(&tokens, capacity)| capacity.is_none_or(|c| tokens <= c))
+            })
+    }
+
+    pub(crate) fn inhibits(&self, t: usize, p: usize) -> bool {
+        self.t_inhibit.as_ref().is_some_and(|m| m[[t, p]] > 0)
+    }
+
+    pub(crate) fn resets(&self, t: usize, p: usize) -> bool {
+        self.t_reset.as_ref().is_some_and(|m| m[[t, p]] > 0)
+    }
+
+    pub(crate) fn reads(&self, t: usize, p: usize) -> bool {
+        self.t_read.as_ref().is_some_and(|m| m[[t, p]] > 0)
+    }
+
+    /// Keeps only the active transitions with the highest priority, if the net has priorities.
+    pub(crate) fn prioritized(&self, mut active_transitions: InputState) -> InputState {
+        if let Some(priorities) = &self.priorities {
+            let highest = active_transitions
+                .iter()
+                .map(|&t| priorities[t as usize])
+                .max();
+            active_transitions.retain(|&t| Some(priorities[t as usize]) == highest);
+        }
+        active_transitions
+    }
+
+    pub(crate) fn capacity(&self, p: usize) -> Option<i16> {
+        self.capacities
+            .as_ref()
+            .and_then(|capacities| capacities[p])
+    }
+
+    /// Returns for each place whether the maximum number of tokens equals its capacity.
+    pub(crate) fn capacity_reached(&self, bounded_vec: &[i16]) -> Vec<bool> {
+        bounded_vec
+            .iter()
+            .enumerate()
+            .map(|(p, &max)| self.capacity(p) == Some(max))
+            .collect()
+    }
+
+    /// Checks whether the net only has input and output arcs, so that the classic structural theory applies.
+    pub(crate) fn is_plain(&self) -> bool {
+        self.t_inhibit.is_none()
+            && self.t_reset.is_none()
+            && self.t_read.is_none()
+            && self.capacities.is_none()
+            && self.priorities.is_none()
+    }
+
+    /// Returns for each place whether additional tokens in it might prevent a firing sequence from being repeated,
+    /// because the place is tested by an inhibitor arc, emptied by a reset arc or limited by a capacity.
+    /// With priorities, any additional tokens might enable a transition with a higher priority.
+    pub(crate) fn non_monotonic_places(&self) -> Vec<bool> {
+        (0..self.place_count())
+            .map(|p| {
+                self.priorities.is_some()
+                    || self.capacity(p).is_some()
+                    || (0..self.transition_count())
+                        .any(|t| self.inhibits(t, p) || self.resets(t, p))
+            })
+            .collect()
+    }
+
+    pub(crate) fn transition_count(&self) -> usize {
+        self.t_in.transition_count()
+    }
+
+    pub(crate) fn place_count(&self) -> usize {
+        self.t_in.place_count()
+    }
+}
+
+fn check_dimensions(t_in: &PTMatrix, other: &InputMatrix, kind: &str) -> Result<(), String> {
+    let matches = other.transition_count() == t_in.transition_count()
+        && other.iter().all(|row| row.len() == t_in.place_count());
+    if !matches {
+        return Err(format!(
+            "❌The {} matrix does not match the input matrix!",
+            kind
+        ));
+    }
+    Ok(())
+}
 
 pub(crate) trait PTDimensions {
     fn transition_count(&self) -> usize;
@@ -170,9 +463,256 @@ impl PTDimensions for InputMatrix {
 /// Response struct to return for a simulation request
 #[derive(Serialize, new)]
 pub struct SimulationResponse {
+    /// The marking of the session. Tokens of timed firings that have started but not completed yet are not part of
+    /// it, because they were consumed from the input places and are not produced in the output places yet.
+    pub marking: InputState,
+    /// How often each transition fired during this request.
+    pub firings: Vec<u64>,
+    pub deadlocked: bool,
+    /// The number of transitions that were fired during this request.
+    pub steps: usize,
+    /// The seed of the random number generator, which can be passed to a new simulation to replay it.
+    pub seed: u64,
+    /// The session of the simulation, which must be passed to continue it.
+    pub session: SessionId,
+    /// The firing sequence of this request, if the simulation was started with trace options.
+    #[new(default)]
+    pub trace: Option<FiringTrace>,
+    /// The elapsed model time of the session, if the net is simulated as a stochastic Petri net.
+    #[new(default)]
+    pub model_time: Option<f64>,
+    /// The marking after every completed firing of this request, if the net is simulated as a timed Petri net.
+    #[new(default)]
+    pub events: Option<Vec<TimedEvent>>,
+    /// Whether the run ended because the marking satisfies the stop condition of the session.
+    #[new(default)]
+    pub stopped: bool,
+    /// Whether the timed run ended because transitions with zero delay kept firing without model time passing.
+    #[new(default)]
+    pub zeno: bool,
+}
+
+impl SimulationResponse {
+    pub(crate) fn with_trace(mut self, trace: Option<FiringTrace>) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    pub(crate) fn with_model_time(mut self, model_time: Option<f64>) -> Self {
+        self.model_time = model_time;
+        self
+    }
+
+    pub(crate) fn with_events(mut self, events: Option<Vec<TimedEvent>>) -> Self {
+        self.events = events;
+        self
+    }
+
+    pub(crate) fn with_stopped(mut self, stopped: bool) -> Self {
+        self.stopped = stopped;
+        self
+    }
+
+    pub(crate) fn with_zeno(mut self, zeno: bool) -> Self {
+        self.zeno = zeno;
+        self
+    }
+}
+
+/// A net as the frontend sends it to every command
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NetInput {
+    pub transition_inputs: InputMatrix,
+    pub transition_outputs: InputMatrix,
+    pub transition_inhibitors: Option<InputMatrix>,
+    pub transition_resets: Option<InputMatrix>,
+    pub transition_reads: Option<InputMatrix>,
+    pub capacities: Option<InputCapacities>,
+    pub transition_priorities: Option<InputState>,
+    /// Names that formulas can use instead of `p3`. The editor does not send them, because it names places `p1`,
+    /// `p2`, … from 1 on, which formulas would read as indices starting at 0.
+    pub place_names: Option<Vec<String>>,
+}
+
+impl NetInput {
+    /// Checks the matrices against each other and converts them into a net.
+    pub(crate) fn into_net(self) -> Result<Net, String> {
+        return Net::new(&self.transition_inputs, &self.transition_outputs)?
+            .with_inhibitors(self.transition_inhibitors)?
+            .with_resets(self.transition_resets)?
+            .with_reads(self.transition_reads)?
+            .with_capacities(self.capacities)?
+            .with_priorities(self.transition_priorities)?
+            .with_place_names(self.place_names);
+    }
+}
+
+/// The options of a new simulation as the frontend sends them. All of them are optional.
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationSettings {
+    pub seed: Option<u64>,
+    pub trace: Option<TraceOptions>,
+    pub stochastic: Option<Vec<StochasticTransition>>,
+    pub timed: Option<Vec<FiringDelay>>,
+    pub conflict_policy: Option<ConflictPolicy>,
+    pub stop_condition: Option<String>,
+}
+
+/// Firing delay of a transition in a timed Petri net
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FiringDelay {
+    /// The firing always takes the given time.
+    Deterministic { delay: f64 },
+    /// The firing takes a uniformly distributed time between `min` and `max`.
+    Interval { min: f64, max: f64 },
+}
+
+/// The marking of a timed simulation at the model time at which a firing completed
+#[derive(Serialize, Debug, new)]
+pub struct TimedEvent {
+    pub time: f64,
+    pub marking: InputState,
+}
+
+/// Timing of a transition in a generalized stochastic Petri net
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StochasticTransition {
+    /// Fires after an exponentially distributed delay with the given rate.
+    Timed { rate: f64 },
+    /// Fires without delay. Conflicts are resolved by priority first and weight second.
+    Immediate { weight: f64, priority: i16 },
+}
+
+/// Decides which of the enabled transitions fires next in a simulation.
+///
+/// Static transition priorities are part of the net, so that the reachability graph respects them as well.
+/// They restrict the candidates before the policy is applied.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Every candidate is equally likely.
+    #[default]
+    Uniform,
+    /// Every candidate is chosen with a probability proportional to its weight.
+    Weighted { weights: Vec<f64> },
+    /// The candidates take turns, starting after the transition that fired last.
+    RoundRobin,
+    /// The candidate with the lowest index always fires.
+    FirstEnabled,
+}
+
+/// The markings a reachability query searches for
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReachabilityQuery {
+    /// Exactly this marking.
+    Marking { marking: InputState },
+    /// Any marking that satisfies all token conditions.
+    Tokens { conditions: Vec<TokenCondition> },
+    /// Any marking that satisfies a condition like `p0 + p1 >= 2 & !dead`.
+    Expression { expression: String },
+}
+
+/// Compares the tokens of a place with a constant, e.g. `p3 >= 2`
+#[derive(Deserialize, Clone, Debug)]
+pub struct TokenCondition {
+    pub place: usize,
+    pub comparison: Comparison,
+    pub tokens: i16,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Less,
+    LessEqual,
+    Equal,
+    NotEqual,
+    GreaterEqual,
+    Greater,
+}
+
+impl Comparison {
+    pub(crate) fn holds<T: PartialOrd>(self, left: T, right: T) -> bool {
+        return match self {
+            Comparison::Less => left < right,
+            Comparison::LessEqual => left <= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::GreaterEqual => left >= right,
+            Comparison::Greater => left > right,
+        };
+    }
+}
+
+/// Request struct that enables trace recording for a simulation
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+pub struct TraceOptions {
+    /// Additionally record the marking after every n-th firing.
+    pub marking_interval: Option<usize>,
+}
+
+/// The recorded firing sequence of a simulation, with optionally sampled intermediate markings
+#[derive(Serialize, Debug)]
+pub struct FiringTrace {
+    pub transitions: InputState,
+    pub markings: Vec<TracedMarking>,
+    #[serde(skip)]
+    marking_interval: Option<usize>,
+}
+
+/// A marking of a trace, which was reached after firing `step` transitions of the trace
+#[derive(Serialize, Debug, new)]
+pub struct TracedMarking {
+    pub step: usize,
+    pub marking: InputState,
+}
+
+impl FiringTrace {
+    pub(crate) fn new(options: &TraceOptions) -> Self {
+        FiringTrace {
+            transitions: Vec::new(),
+            markings: Vec::new(),
+            marking_interval: options.marking_interval.filter(|&interval| interval > 0),
+        }
+    }
+
+    /// Records a fired transition and the marking it produced.
+    pub(crate) fn record(&mut self, transition: usize, marking: &State) {
+        self.transitions.push(transition as i16);
+        let step = self.transitions.len();
+        if self
+            .marking_interval
+            .is_some_and(|interval| step.is_multiple_of(interval))
+        {
+            self.markings
+                .push(TracedMarking::new(step, marking.to_vec()));
+        }
+    }
+}
+
+/// Response struct to return for a trace replay request
+#[derive(Serialize, new)]
+pub struct ReplayResponse {
+    /// The marking after firing all transitions, or the marking in which the replay got stuck.
+    pub marking: InputState,
+    /// The number of transitions of the trace that could be fired.
+    pub fired: usize,
+    /// The first transition of the trace that was not enabled, if any.
+    pub blocked_transition: Option<i16>,
+}
+
+/// Response struct that describes a running simulation session
+#[derive(Serialize, new)]
+pub struct SimulationSession {
+    pub session: SessionId,
     pub marking: InputState,
-    pub firings: InputState,
     pub deadlocked: bool,
+    pub seed: u64,
 }
 
 /// Response struct to return for a RG request
@@ -180,19 +720,247 @@ pub struct SimulationResponse {
 pub struct RGResponse {
     pub states: usize,
     pub edges: usize,
-    pub reversible: bool,
-    pub liveness: bool,
+    /// Whether the net is reversible, or None for a coverability graph, which can not decide it.
+    pub reversible: Option<bool>,
+    /// Whether the net is live, or None for a coverability graph, which can not decide it.
+    pub liveness: Option<bool>,
     pub bounded: i16,
     pub bounded_vec: Vec<i16>,
-    pub has_deadlock: bool,
+    /// Whether the maximum number of tokens of each place equals its capacity.
+    pub capacity_reached: Vec<bool>,
+    /// Whether a reachable marking is dead. A coverability graph only proves deadlocks, so it reports None if it finds
+    /// none.
+    pub has_deadlock: Option<bool>,
     pub dot_graph: String,
     pub message: String,
+    /// Explains why the net is unbounded, if a covering firing sequence was found.
+    pub unbounded_witness: Option<UnboundedWitness>,
+    /// Every dead marking of the graph with the shortest firing sequence that reaches it.
+    pub deadlocks: Vec<Deadlock>,
+    /// The liveness level of each transition. Only the reachability graph provides them.
+    pub transition_liveness: Vec<TransitionLiveness>,
+    /// The markings that are reachable from every reachable marking.
+    pub home_states: Vec<InputState>,
+    /// Explains why the net is not reversible, if the reachability graph shows it.
+    pub reversibility_witness: Option<ReversibilityWitness>,
+}
+
+/// A reachable marking from which the initial marking can not be reached again, and the shortest firing sequence to it
+#[derive(Serialize, Debug, Clone, new)]
+pub struct ReversibilityWitness {
+    pub marking: InputState,
+    pub firing_sequence: InputState,
+}
+
+/// How often a transition can fire, from dead (L0) to live (L4)
+#[derive(Serialize, Debug, Clone, new)]
+pub struct TransitionLiveness {
+    /// The highest liveness level the transition has. L2 and L3 coincide for finite reachability graphs, so the
+    /// level is never 2.
+    pub level: u8,
+    /// A marking from which the transition can never fire again, if it is not live. It belongs to a terminal strongly
+    /// connected component of the reachability graph.
+    pub never_fires_from: Option<InputState>,
+}
+
+/// A reachable marking in which no transition is enabled. Unbounded places of a coverability graph node have -1
+/// tokens.
+#[derive(Serialize, Debug, new)]
+pub struct Deadlock {
+    pub marking: InputState,
+    pub firing_sequence: InputState,
+}
+
+/// Proof that a net is unbounded. Firing the sequence from the initial marking passes the ancestor marking after
+/// `ancestor_step` firings and ends in the covering marking, which strictly covers the ancestor. Repeating the firings
+/// after the ancestor therefore pumps the growing places arbitrarily high.
+#[derive(Serialize, Debug, new)]
+pub struct UnboundedWitness {
+    pub firing_sequence: InputState,
+    pub ancestor_step: usize,
+    pub ancestor: InputState,
+    pub covering: InputState,
+    pub growing_places: Vec<usize>,
+}
+
+/// Response struct to return for a state equation request
+#[derive(Serialize, Debug, new)]
+pub struct StateEquationResponse {
+    /// Whether the target is reachable, or None if the state equation can not decide it.
+    pub reachable: Option<bool>,
+    /// How often each transition fires in the smallest solution of the state equation.
+    pub parikh_vector: Option<Vec<i64>>,
+    /// A firing sequence from the initial marking to the target that realizes the Parikh vector.
+    pub firing_sequence: Option<InputState>,
+    pub message: String,
+}
+
+/// Response struct to return for a reachability query
+#[derive(Serialize, Debug, new)]
+pub struct ReachabilityResponse {
+    /// Whether a marking satisfies the query, or None if the search stopped before exploring all markings.
+    pub reachable: Option<bool>,
+    /// The shortest firing sequence to a marking that satisfies the query.
+    pub firing_sequence: Option<InputState>,
+    /// The markings along the firing sequence, starting with the initial marking.
+    pub markings: Option<Vec<InputState>>,
+    /// The number of markings the search explored.
+    pub states: usize,
+    pub message: String,
+}
+
+/// Response struct to return for a CTL model checking request
+#[derive(Serialize, Debug, new)]
+pub struct CtlResponse {
+    /// Whether the initial marking satisfies the formula.
+    pub holds: bool,
+    /// The markings of the reachability graph that satisfy the formula.
+    pub satisfying_states: Vec<InputState>,
+    pub states: usize,
+    pub message: String,
+}
+
+/// A position of a run: the marking and the transition that fires in it, which is missing for a dead marking
+#[derive(Serialize, Debug, Clone, new)]
+pub struct LassoStep {
+    pub marking: InputState,
+    pub transition: Option<i16>,
+}
+
+/// An infinite run that violates a formula: the stem is followed by the cycle, which repeats forever
+#[derive(Serialize, Debug, new)]
+pub struct Lasso {
+    pub stem: Vec<LassoStep>,
+    pub cycle: Vec<LassoStep>,
+}
+
+/// Response struct to return for an LTL model checking request
+#[derive(Serialize, Debug, new)]
+pub struct LtlResponse {
+    /// Whether every run from the initial marking satisfies the formula.
+    pub holds: bool,
+    pub counterexample: Option<Lasso>,
+    /// The number of states in the product of the reachability graph and the automaton.
+    pub states: usize,
+    pub message: String,
+}
+
+/// Response struct to return for a structural analysis request
+#[derive(Serialize)]
+pub struct StructuralResponse {
+    pub p_invariants: Vec<PInvariant>,
+    /// The upper bound of each place that follows from the P-invariants and capacities, if there is one.
+    pub place_bounds: Vec<Option<i64>>,
+    /// The minimal T-invariants with one weight per transition.
+    pub t_invariants: Vec<Vec<i64>>,
+    /// Whether every transition is covered by a T-invariant. Every live and bounded net is consistent.
+    pub consistent: bool,
+    /// Whether there is a firing count vector with only positive entries that does not decrease any marking.
+    /// Every live net with a finite initial marking is repetitive.
+    pub repetitive: bool,
+    pub siphons: Vec<Siphon>,
+    /// Whether every siphon contains an initially marked trap. Then no reachable marking of an ordinary net is
+    /// a deadlock, and a free-choice net is even live (Commoner's theorem).
+    pub siphons_contain_marked_traps: bool,
+    /// The union of all traps of the net.
+    pub maximal_trap: Vec<usize>,
+    pub classification: NetClassification,
+    /// The rank theorem analysis, if the net is an ordinary free-choice net without special arcs.
+    pub free_choice: Option<FreeChoiceAnalysis>,
+}
+
+/// Liveness and boundedness of a free-choice net, decided by the rank theorem and Commoner's theorem
+#[derive(Serialize, Debug)]
+pub struct FreeChoiceAnalysis {
+    pub strongly_connected: bool,
+    pub consistent: bool,
+    /// Whether every place is covered by a P-invariant.
+    pub conservative: bool,
+    /// The rank of the incidence matrix.
+    pub rank: usize,
+    pub clusters: usize,
+    /// Whether the rank is the number of clusters minus one.
+    pub rank_condition: bool,
+    /// Whether there is a marking that makes the net live and bounded.
+    pub well_formed: bool,
+    /// Whether the net is live with the initial marking, by Commoner's theorem.
+    pub live: Option<bool>,
+    /// Whether the net is bounded with the initial marking, if that can be decided structurally.
+    pub bounded: Option<bool>,
+}
+
+/// The syntactic subclasses of the net
+#[derive(Serialize, Debug, new)]
+pub struct NetClassification {
+    pub ordinary: ClassMembership,
+    pub state_machine: ClassMembership,
+    pub marked_graph: ClassMembership,
+    pub free_choice: ClassMembership,
+    pub extended_free_choice: ClassMembership,
+    pub asymmetric_choice: ClassMembership,
+}
+
+/// Whether the net belongs to a class, or else the places and transitions that violate it
+#[derive(Serialize, Debug)]
+pub struct ClassMembership {
+    pub member: bool,
+    pub offending_places: Vec<usize>,
+    pub offending_transitions: Vec<usize>,
+}
+
+impl ClassMembership {
+    pub(crate) fn new(offending_places: Vec<usize>, offending_transitions: Vec<usize>) -> Self {
+        ClassMembership {
+            member: offending_places.is_empty() && offending_transitions.is_empty(),
+            offending_places,
+            offending_transitions,
+        }
+    }
+}
+
+/// A minimal siphon, which can never be marked again once it is empty
+#[derive(Serialize, Debug, new)]
+pub struct Siphon {
+    pub places: Vec<usize>,
+    /// The largest trap within the siphon.
+    pub maximal_trap: Vec<usize>,
+    /// Whether the maximal trap is marked initially, so that the siphon can never be emptied. Otherwise, the siphon is
+    /// not protected, but it is not necessarily emptied either.
+    pub has_marked_trap: bool,
+    /// Whether a reachable marking empties the siphon. Only decided for ordinary free-choice nets, where exactly the
+    /// siphons without a marked trap can be emptied.
+    #[new(default)]
+    pub can_be_emptied: Option<bool>,
+}
+
+impl Siphon {
+    pub(crate) fn with_can_be_emptied(mut self, can_be_emptied: Option<bool>) -> Self {
+        self.can_be_emptied = can_be_emptied;
+        self
+    }
 }
 
-/// An internal struct that describes the result of a RG generation
+/// A minimal P-invariant with one weight per place
+#[derive(Serialize, Debug, new)]
+pub struct PInvariant {
+    pub weights: Vec<i64>,
+    /// The weighted token sum of the initial marking. It stays the same in all reachable markings, unless reset arcs
+    /// remove tokens, so in nets with reset arcs it is only an upper bound.
+    pub token_sum: i64,
+}
+
+/// An internal struct that describes the result of a RG generation, or of a coverability graph generation
+/// where the nodes may contain [OMEGA]
 pub struct RGResult {
     pub rg: ReachabilityGraph,
-    pub has_deadlock: bool,
+    /// The nodes without enabled transitions.
+    pub dead_nodes: Vec<NodeIndex>,
+}
+
+impl RGResult {
+    pub(crate) fn has_deadlock(&self) -> bool {
+        !self.dead_nodes.is_empty()
+    }
 }
 
 #[derive(Debug)]
@@ -202,6 +970,9 @@ pub struct RGProperties {
     pub bounded_vec: Vec<i16>,
     pub k_bounded: i16,
     pub has_deadlock: bool,
+    pub transition_liveness: Vec<TransitionLiveness>,
+    pub home_states: Vec<InputState>,
+    pub reversibility_witness: Option<ReversibilityWitness>,
 }
 
 impl RGResponse {
@@ -209,32 +980,87 @@ impl RGResponse {
         RGResponse {
             states: 0,
             edges: 0,
-            reversible: false,
-            liveness: false,
+            reversible: None,
+            liveness: None,
             bounded: -1,
             bounded_vec: Vec::new(),
-            has_deadlock: false,
+            capacity_reached: Vec::new(),
+            has_deadlock: None,
             dot_graph: "".to_string(),
             message: "Graph is unbounded".to_string(),
+            unbounded_witness: None,
+            deadlocks: Vec::new(),
+            transition_liveness: Vec::new(),
+            home_states: Vec::new(),
+            reversibility_witness: None,
+        }
+    }
+
+    pub(crate) fn with_unbounded_witness(mut self, witness: Option<UnboundedWitness>) -> Self {
+        self.unbounded_witness = witness;
+        self
+    }
+
+    pub(crate) fn with_deadlocks(mut self, deadlocks: Vec<Deadlock>) -> Self {
+        self.deadlocks = deadlocks;
+        self
+    }
+
+    /// Describes the coverability graph of an unbounded net. Unbounded places have a bound of -1. Liveness and
+    /// reversibility stay unknown, and a dead node without ω is a reachable deadlock, but no dead node does not rule
+    /// deadlocks out.
+    pub(crate) fn coverability(
+        result: &RGResult,
+        bounded_vec: Vec<i16>,
+        dot_graph: String,
+        message: String,
+    ) -> Self {
+        RGResponse {
+            states: result.rg.node_count(),
+            edges: result.rg.edge_count(),
+            reversible: None,
+            liveness: None,
+            bounded: -1,
+            capacity_reached: vec![false; bounded_vec.len()],
+            bounded_vec,
+            has_deadlock: result
+                .dead_nodes
+                .iter()
+                .any(|&node| !result.rg[node].iter().any(|&tokens| tokens == OMEGA))
+                .then_some(true),
+            dot_graph,
+            message,
+            unbounded_witness: None,
+            deadlocks: Vec::new(),
+            transition_liveness: Vec::new(),
+            home_states: Vec::new(),
+            reversibility_witness: None,
         }
     }
 
     pub(crate) fn success(
         graph: &ReachabilityGraph,
         properties: &RGProperties,
+        capacity_reached: Vec<bool>,
         dot_graph: String,
         message: String,
     ) -> Self {
         RGResponse {
             states: graph.node_count(),
             edges: graph.edge_count(),
-            reversible: properties.reversible,
-            liveness: properties.liveness,
+            reversible: Some(properties.reversible),
+            liveness: Some(properties.liveness),
             bounded: properties.k_bounded,
             bounded_vec: properties.bounded_vec.clone(),
-            has_deadlock: properties.has_deadlock,
+            capacity_reached,
+            has_deadlock: Some(properties.has_deadlock),
             dot_graph,
             message,
+            unbounded_witness: None,
+            deadlocks: Vec::new(),
+            transition_liveness: properties.transition_liveness.clone(),
+            home_states: properties.home_states.clone(),
+            reversibility_witness: properties.reversibility_witness.clone(),
         }
     }
 }
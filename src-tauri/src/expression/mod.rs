@@ -0,0 +1,123 @@
+use crate::common::*;
+pub(crate) use crate::expression::parser::{check_place_name, Parser};
+
+mod mod_test;
+mod parser;
+
+/// A predicate or number over a marking, like `p0 + p1 >= 2 & !enabled(t3)`.
+///
+/// Reachability queries, simulation stop conditions and the atoms of the temporal logics are all written in this
+/// language.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expression {
+    Constant(bool),
+    Number(i64),
+    /// The tokens of a place, written as `p3` or as the name of the place.
+    Tokens(usize),
+    /// `enabled(t4)` holds if the transition can fire in the marking, which respects priorities.
+    Enabled(i16),
+    /// `dead` holds if no transition can fire in the marking.
+    Dead,
+    Negate(Box<Expression>),
+    Arithmetic(Operator, Box<Expression>, Box<Expression>),
+    Compare(Comparison, Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Type {
+    Number,
+    Boolean,
+}
+
+impl Type {
+    pub(crate) fn describe(self) -> &'static str {
+        return match self {
+            Type::Number => "a number",
+            Type::Boolean => "a condition",
+        };
+    }
+}
+
+impl Expression {
+    pub(crate) fn type_of(&self) -> Type {
+        return match self {
+            Expression::Number(_)
+            | Expression::Tokens(_)
+            | Expression::Negate(_)
+            | Expression::Arithmetic(..) => Type::Number,
+            _ => Type::Boolean,
+        };
+    }
+
+    /// Evaluates a number in the marking. Division by zero and overflows have no value.
+    pub(crate) fn value(&self, state: &State) -> Option<i64> {
+        return match self {
+            Expression::Number(number) => Some(*number),
+            Expression::Tokens(place) => Some(state[*place] as i64),
+            Expression::Negate(inner) => inner.value(state)?.checked_neg(),
+            Expression::Arithmetic(operator, left, right) => {
+                let (left, right) = (left.value(state)?, right.value(state)?);
+                match operator {
+                    Operator::Add => left.checked_add(right),
+                    Operator::Subtract => left.checked_sub(right),
+                    Operator::Multiply => left.checked_mul(right),
+                    Operator::Divide => left.checked_div(right),
+                    Operator::Remainder => left.checked_rem(right),
+                }
+            }
+            _ => unreachable!("the type checker only evaluates numbers"),
+        };
+    }
+
+    /// Evaluates a condition in the marking. A comparison with a number that has no value does not hold.
+    pub(crate) fn holds(&self, state: &State, net: &Net) -> bool {
+        return match self {
+            Expression::Constant(value) => *value,
+            Expression::Enabled(t) => net
+                .prioritized(find_active_transitions(state, net))
+                .contains(t),
+            Expression::Dead => net
+                .prioritized(find_active_transitions(state, net))
+                .is_empty(),
+            Expression::Compare(comparison, left, right) => {
+                match (left.value(state), right.value(state)) {
+                    (Some(left), Some(right)) => comparison.holds(left, right),
+                    _ => false,
+                }
+            }
+            Expression::Not(inner) => !inner.holds(state, net),
+            Expression::And(left, right) => left.holds(state, net) && right.holds(state, net),
+            Expression::Or(left, right) => left.holds(state, net) || right.holds(state, net),
+            _ => unreachable!("the type checker only evaluates conditions"),
+        };
+    }
+}
+
+/// Combines the conditions into one that holds if all of them hold.
+pub(crate) fn all(conditions: impl IntoIterator<Item = Expression>) -> Expression {
+    return conditions
+        .into_iter()
+        .reduce(|left, right| Expression::And(Box::new(left), Box::new(right)))
+        .unwrap_or(Expression::Constant(true));
+}
+
+/// Parses a condition like `p0 + p1 >= 2 & !dead`, which is used by reachability queries and simulation stop
+/// conditions.
+pub(crate) fn parse_condition(text: &str, net: &Net) -> Result<Expression, String> {
+    let mut parser = Parser::new(text, net)?;
+    let condition = parser.parse_expression(Type::Boolean)?;
+    parser.expect_end()?;
+    return Ok(condition);
+}
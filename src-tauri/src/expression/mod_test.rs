@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests {
+    use ndarray::arr1;
+
+    use crate::common::*;
+    use crate::expression::*;
+
+    fn server() -> Net {
+        // A server that is idle or busy, and a queue of jobs
+        // (idle)──>███──>(busy)──>███──>(idle)
+        // (queue)───┘
+        Net::new(
+            &vec![vec![1, 0, 1], vec![0, 1, 0]],
+            &vec![vec![0, 1, 0], vec![1, 0, 0]],
+        )
+        .unwrap()
+        .with_place_names(Some(vec![
+            "idle".to_string(),
+            "busy".to_string(),
+            "queue".to_string(),
+        ]))
+        .unwrap()
+    }
+
+    fn holds(condition: &str, marking: InputState) -> bool {
+        let net = server();
+        parse_condition(condition, &net)
+            .unwrap()
+            .holds(&arr1(&marking), &net)
+    }
+
+    #[test]
+    fn evaluates_arithmetic_and_comparisons() {
+        assert!(holds("p2 + 2 * p1 == 7", vec![0, 3, 1]));
+        assert!(holds("(p2 + 2) * p1 == 9", vec![0, 3, 1]));
+        assert!(holds("p2 - p1 < 0 & -p1 == -3", vec![0, 3, 1]));
+        assert!(holds("p2 % 2 == 1 & p2 / 2 == 2", vec![0, 0, 5]));
+        assert!(!holds("p0 != 0 | !(p1 > 0)", vec![0, 3, 1]));
+    }
+
+    #[test]
+    fn resolves_place_names() {
+        assert!(holds("idle + busy == 1 & queue >= 2", vec![1, 0, 2]));
+        assert!(holds("queue > p0", vec![1, 0, 2]));
+
+        let net = server();
+        let unknown = parse_condition("queues > 0", &net).unwrap_err();
+        assert_eq!(
+            unknown,
+            "❌Expected a place, number or condition at position 1, but found 'queues'!"
+        );
+    }
+
+    #[test]
+    fn place_names_can_not_collide_with_formulas() {
+        let named = |name: &str| {
+            Net::new(&vec![vec![1, 0]], &vec![vec![0, 1]])
+                .unwrap()
+                .with_place_names(Some(vec!["idle".to_string(), name.to_string()]))
+        };
+        for name in ["p0", "t12", "dead", "en", "G", "EF", "fired"] {
+            let error = named(name).err().unwrap();
+            assert_eq!(
+                error,
+                format!(
+                    "❌Place 1 can not be named '{}', because formulas use that word!",
+                    name
+                )
+            );
+        }
+        assert!(named("p").is_ok());
+        assert!(named("p1a").is_ok());
+        assert!(named("Gate").is_ok());
+        assert!(named("_buffer_2").is_ok());
+    }
+
+    #[test]
+    fn place_names_are_identifiers() {
+        let named = |name: &str| {
+            Net::new(&vec![vec![1, 0]], &vec![vec![0, 1]])
+                .unwrap()
+                .with_place_names(Some(vec!["idle".to_string(), name.to_string()]))
+        };
+        for name in ["Buffer 1", "", "2nd", "a-b", "büro"] {
+            let error = named(name).err().unwrap();
+            assert_eq!(
+                error,
+                format!(
+                    "❌Place 1 can not be named '{}', because names consist of letters, digits and underscores \
+                     and do not start with a digit!",
+                    name
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn place_names_are_unique() {
+        let error = Net::new(&vec![vec![1, 0, 0]], &vec![vec![0, 1, 0]])
+            .unwrap()
+            .with_place_names(Some(vec![
+                "a".to_string(),
+                "b".to_string(),
+                "a".to_string(),
+            ]))
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            error,
+            "❌Place 2 can not be named 'a', because place 0 already has that name!"
+        );
+    }
+
+    #[test]
+    fn evaluates_enabled_and_dead() {
+        assert!(holds("enabled(t0) & !enabled(t1)", vec![1, 0, 1]));
+        assert!(holds("en(t1) & !dead", vec![0, 1, 0]));
+        assert!(holds("dead", vec![1, 0, 0]));
+    }
+
+    #[test]
+    fn comparisons_without_a_value_do_not_hold() {
+        assert!(!holds("p0 / p1 == 0", vec![1, 0, 0]));
+        assert!(holds("!(p0 % p1 == 0)", vec![1, 0, 0]));
+        assert!(!holds("p0 * 9223372036854775807 * 2 > 0", vec![1, 0, 0]));
+    }
+
+    #[test]
+    fn reports_type_errors_with_positions() {
+        let net = server();
+        let number = parse_condition("idle + 1", &net).unwrap_err();
+        assert_eq!(
+            number,
+            "❌Expected a condition at position 1, but found a number!"
+        );
+
+        let operand = parse_condition("p0 + dead > 1", &net).unwrap_err();
+        assert_eq!(
+            operand,
+            "❌Expected a number at position 6, but found a condition!"
+        );
+
+        let connective = parse_condition("p0 >= 1 & p1", &net).unwrap_err();
+        assert_eq!(
+            connective,
+            "❌Expected a condition at position 11, but found a number!"
+        );
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_positions() {
+        let net = server();
+        let missing = parse_condition("p0 >= ", &net).unwrap_err();
+        assert_eq!(
+            missing,
+            "❌Expected a place, number or condition at position 7, but found the end!"
+        );
+
+        let transition = parse_condition("enabled(t5)", &net).unwrap_err();
+        assert_eq!(transition, "❌The net has no t5 at position 9!");
+
+        let chained = parse_condition("0 < p0 < 2", &net).unwrap_err();
+        assert_eq!(
+            chained,
+            "❌Expected the end of the formula at position 8, but found '<'!"
+        );
+
+        let character = parse_condition("p0 >= 1 ; p1 >= 1", &net).unwrap_err();
+        assert_eq!(character, "❌Unexpected character ';' at position 9!");
+    }
+}
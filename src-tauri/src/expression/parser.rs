@@ -0,0 +1,405 @@
+use crate::common::*;
+use crate::expression::{Expression, Operator, Type};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(i64),
+    Symbol(&'static str),
+}
+
+/// The symbols of the formula languages, longer ones first so that they take precedence
+const SYMBOLS: [&str; 22] = [
+    "->", "<=", ">=", "==", "!=", "&&", "||", "(", ")", "[", "]", "!", "&", "|", "<", ">", "=",
+    "+", "-", "*", "/", "%",
+];
+
+/// The keywords of the expressions and the temporal logics, which can not be used as place names
+const KEYWORDS: [&str; 19] = [
+    "true", "false", "dead", "enabled", "en", "EX", "AX", "EF", "AF", "EG", "AG", "E", "A", "U",
+    "X", "F", "G", "R", "fired",
+];
+
+/// Checks that formulas can refer to the place by its name. The name has to be a word of letters, digits and
+/// underscores that does not start with a digit, and it must not be a keyword or look like `p3` or `t4`.
+pub(crate) fn check_place_name(place: usize, name: &str) -> Result<(), String> {
+    let is_word = name
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_word {
+        return Err(format!(
+            "❌Place {} can not be named '{}', because names consist of letters, digits and underscores and do not \
+             start with a digit!",
+            place, name
+        ));
+    }
+    let is_index = |prefix: char| {
+        name.strip_prefix(prefix)
+            .is_some_and(|digits| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()))
+    };
+    if KEYWORDS.contains(&name) || is_index('p') || is_index('t') {
+        return Err(format!(
+            "❌Place {} can not be named '{}', because formulas use that word!",
+            place, name
+        ));
+    }
+    return Ok(());
+}
+
+/// A recursive descent parser for expressions over the places and transitions of a net. The temporal logics build
+/// their grammars on top of its tokens and conditions.
+///
+/// The parser checks the types while it builds the expression, so that type errors report positions too.
+pub(crate) struct Parser {
+    /// The tokens with their positions in the text, starting at 1
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+    end: usize,
+    places: usize,
+    transitions: usize,
+    place_names: Vec<String>,
+}
+
+impl Parser {
+    pub(crate) fn new(text: &str, net: &Net) -> Result<Self, String> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let start = i;
+            if chars[i].is_whitespace() {
+                i += 1;
+                continue;
+            }
+            if chars[i].is_ascii_alphabetic() || chars[i] == '_' {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word = chars[start..i].iter().collect();
+                tokens.push((Token::Word(word), start + 1));
+            } else if chars[i].is_ascii_digit() {
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let digits: String = chars[start..i].iter().collect();
+                let number = digits
+                    .parse()
+                    .map_err(|_| format!("❌The number at position {} is too large!", start + 1))?;
+                tokens.push((Token::Number(number), start + 1));
+            } else {
+                let symbol = SYMBOLS.iter().find(|symbol| {
+                    symbol
+                        .chars()
+                        .enumerate()
+                        .all(|(offset, c)| chars.get(i + offset) == Some(&c))
+                });
+                let Some(&symbol) = symbol else {
+                    return Err(format!(
+                        "❌Unexpected character '{}' at position {}!",
+                        chars[i],
+                        start + 1
+                    ));
+                };
+                i += symbol.chars().count();
+                let symbol = match symbol {
+                    "&&" => "&",
+                    "||" => "|",
+                    "=" => "==",
+                    symbol => symbol,
+                };
+                tokens.push((Token::Symbol(symbol), start + 1));
+            }
+        }
+
+        return Ok(Parser {
+            tokens,
+            next: 0,
+            end: chars.len() + 1,
+            places: net.place_count(),
+            transitions: net.transition_count(),
+            place_names: net.place_names.clone().unwrap_or_default(),
+        });
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        return self.tokens.get(self.next).map(|(token, _)| token);
+    }
+
+    fn position(&self) -> usize {
+        return self
+            .tokens
+            .get(self.next)
+            .map_or(self.end, |&(_, position)| position);
+    }
+
+    /// Describes what was expected at the current position and what was found instead.
+    pub(crate) fn error(&self, expected: &str) -> String {
+        let found = match self.peek() {
+            Some(Token::Word(word)) => format!("'{}'", word),
+            Some(Token::Number(number)) => format!("'{}'", number),
+            Some(Token::Symbol(symbol)) => format!("'{}'", symbol),
+            None => "the end".to_string(),
+        };
+        return format!(
+            "❌Expected {} at position {}, but found {}!",
+            expected,
+            self.position(),
+            found
+        );
+    }
+
+    /// Consumes the next token if it is the symbol.
+    pub(crate) fn accept_symbol(&mut self, symbol: &str) -> bool {
+        let accepted = matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol);
+        if accepted {
+            self.next += 1;
+        }
+        return accepted;
+    }
+
+    pub(crate) fn expect_symbol(&mut self, symbol: &str) -> Result<(), String> {
+        if !self.accept_symbol(symbol) {
+            return Err(self.error(&format!("'{}'", symbol)));
+        }
+        return Ok(());
+    }
+
+    /// Consumes the next token if it is the keyword.
+    pub(crate) fn accept_word(&mut self, keyword: &str) -> bool {
+        let accepted = matches!(self.peek(), Some(Token::Word(word)) if word == keyword);
+        if accepted {
+            self.next += 1;
+        }
+        return accepted;
+    }
+
+    pub(crate) fn expect_end(&self) -> Result<(), String> {
+        if self.peek().is_some() {
+            return Err(self.error("the end of the formula"));
+        }
+        return Ok(());
+    }
+
+    /// Runs the parse function and rewinds to the current token if it fails.
+    pub(crate) fn try_parse<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Parser) -> Result<T, String>,
+    ) -> Option<T> {
+        let next = self.next;
+        let result = parse(self).ok();
+        if result.is_none() {
+            self.next = next;
+        }
+        return result;
+    }
+
+    /// Parses a full expression of the given type, including the boolean connectives `!`, `&` and `|`.
+    pub(crate) fn parse_expression(&mut self, expected: Type) -> Result<Expression, String> {
+        let position = self.position();
+        let expression = self.parse_or()?;
+        return self.check_type(expression, expected, position);
+    }
+
+    /// Parses a condition without the boolean connectives at the top, like `p3 >= 2`, `(p0 + p1) % 2 == 0` or
+    /// `dead`, so that the temporal logics can combine it with their own operators.
+    pub(crate) fn parse_predicate(&mut self) -> Result<Expression, String> {
+        let position = self.position();
+        let predicate = self.parse_comparison()?;
+        return self.check_type(predicate, Type::Boolean, position);
+    }
+
+    fn check_type(
+        &self,
+        expression: Expression,
+        expected: Type,
+        position: usize,
+    ) -> Result<Expression, String> {
+        if expression.type_of() != expected {
+            return Err(format!(
+                "❌Expected {} at position {}, but found {}!",
+                expected.describe(),
+                position,
+                expression.type_of().describe()
+            ));
+        }
+        return Ok(expression);
+    }
+
+    /// Parses the next operand and checks its type.
+    fn parse_operand(
+        &mut self,
+        parse: impl FnOnce(&mut Parser) -> Result<Expression, String>,
+        expected: Type,
+    ) -> Result<Box<Expression>, String> {
+        let position = self.position();
+        let operand = parse(self)?;
+        return Ok(Box::new(self.check_type(operand, expected, position)?));
+    }
+
+    fn parse_or(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_and()?;
+        while self.accept_symbol("|") {
+            let left = Box::new(expression);
+            let right = self.parse_operand(Parser::parse_and, Type::Boolean)?;
+            expression = Expression::Or(left, right);
+        }
+        return Ok(expression);
+    }
+
+    fn parse_and(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_not()?;
+        while self.accept_symbol("&") {
+            let left = Box::new(expression);
+            let right = self.parse_operand(Parser::parse_not, Type::Boolean)?;
+            expression = Expression::And(left, right);
+        }
+        return Ok(expression);
+    }
+
+    fn parse_not(&mut self) -> Result<Expression, String> {
+        if self.accept_symbol("!") {
+            let inner = self.parse_operand(Parser::parse_not, Type::Boolean)?;
+            return Ok(Expression::Not(inner));
+        }
+        return self.parse_comparison();
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expression, String> {
+        let position = self.position();
+        let left = self.parse_sum()?;
+        let comparison = match self.peek() {
+            Some(Token::Symbol("<")) => Comparison::Less,
+            Some(Token::Symbol("<=")) => Comparison::LessEqual,
+            Some(Token::Symbol("==")) => Comparison::Equal,
+            Some(Token::Symbol("!=")) => Comparison::NotEqual,
+            Some(Token::Symbol(">=")) => Comparison::GreaterEqual,
+            Some(Token::Symbol(">")) => Comparison::Greater,
+            _ => return Ok(left),
+        };
+        self.next += 1;
+        let left = Box::new(self.check_type(left, Type::Number, position)?);
+        let right = self.parse_operand(Parser::parse_sum, Type::Number)?;
+        return Ok(Expression::Compare(comparison, left, right));
+    }
+
+    fn parse_sum(&mut self) -> Result<Expression, String> {
+        let position = self.position();
+        let mut expression = self.parse_product()?;
+        loop {
+            let operator = match self.peek() {
+                Some(Token::Symbol("+")) => Operator::Add,
+                Some(Token::Symbol("-")) => Operator::Subtract,
+                _ => return Ok(expression),
+            };
+            expression =
+                self.parse_arithmetic(expression, position, operator, Parser::parse_product)?;
+        }
+    }
+
+    fn parse_product(&mut self) -> Result<Expression, String> {
+        let position = self.position();
+        let mut expression = self.parse_factor()?;
+        loop {
+            let operator = match self.peek() {
+                Some(Token::Symbol("*")) => Operator::Multiply,
+                Some(Token::Symbol("/")) => Operator::Divide,
+                Some(Token::Symbol("%")) => Operator::Remainder,
+                _ => return Ok(expression),
+            };
+            expression =
+                self.parse_arithmetic(expression, position, operator, Parser::parse_factor)?;
+        }
+    }
+
+    /// Parses the right operand after the operator and checks that both operands are numbers.
+    fn parse_arithmetic(
+        &mut self,
+        left: Expression,
+        position: usize,
+        operator: Operator,
+        parse_right: impl FnOnce(&mut Parser) -> Result<Expression, String>,
+    ) -> Result<Expression, String> {
+        let left = Box::new(self.check_type(left, Type::Number, position)?);
+        self.next += 1;
+        let right = self.parse_operand(parse_right, Type::Number)?;
+        return Ok(Expression::Arithmetic(operator, left, right));
+    }
+
+    /// Parses a number, a place, `true`, `false`, `dead`, `enabled(t4)`, a negation like `-p2` or an expression in
+    /// parentheses.
+    fn parse_factor(&mut self) -> Result<Expression, String> {
+        if self.accept_symbol("-") {
+            let inner = self.parse_operand(Parser::parse_factor, Type::Number)?;
+            return Ok(Expression::Negate(inner));
+        }
+        if self.accept_symbol("(") {
+            let expression = self.parse_or()?;
+            self.expect_symbol(")")?;
+            return Ok(expression);
+        }
+        if let Some(&Token::Number(number)) = self.peek() {
+            self.next += 1;
+            return Ok(Expression::Number(number));
+        }
+        if self.accept_word("true") {
+            return Ok(Expression::Constant(true));
+        }
+        if self.accept_word("false") {
+            return Ok(Expression::Constant(false));
+        }
+        if self.accept_word("dead") {
+            return Ok(Expression::Dead);
+        }
+        if self.accept_word("enabled") || self.accept_word("en") {
+            return Ok(Expression::Enabled(self.parse_transition()?));
+        }
+        return Ok(Expression::Tokens(self.parse_place()?));
+    }
+
+    /// Parses a place by its name or like `p3`.
+    fn parse_place(&mut self) -> Result<usize, String> {
+        if let Some(Token::Word(word)) = self.peek() {
+            // the net makes sure that place names are unique
+            if let Some(place) = self.place_names.iter().position(|name| name == word) {
+                self.next += 1;
+                return Ok(place);
+            }
+        }
+        return self.parse_index('p', self.places, "a place, number or condition");
+    }
+
+    /// Parses a transition in parentheses like `(t4)`.
+    pub(crate) fn parse_transition(&mut self) -> Result<i16, String> {
+        self.expect_symbol("(")?;
+        let t = self.parse_index('t', self.transitions, "a transition like t4")?;
+        self.expect_symbol(")")?;
+        return Ok(t as i16);
+    }
+
+    /// Parses a place or transition like `p3` or `t4` and checks that it exists.
+    fn parse_index(&mut self, prefix: char, count: usize, expected: &str) -> Result<usize, String> {
+        let index = match self.peek() {
+            Some(Token::Word(word)) => word
+                .strip_prefix(prefix)
+                .and_then(|digits| digits.parse::<usize>().ok()),
+            _ => None,
+        };
+        match index {
+            Some(index) if index < count => {
+                self.next += 1;
+                return Ok(index);
+            }
+            Some(index) => {
+                return Err(format!(
+                    "❌The net has no {}{} at position {}!",
+                    prefix,
+                    index,
+                    self.position()
+                ))
+            }
+            None => return Err(self.error(expected)),
+        }
+    }
+}
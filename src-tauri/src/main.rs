@@ -1,11 +1,18 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use crate::common::{InputMatrix, InputState, RGResponse, SimulationResponse};
+use crate::common::{
+    CtlResponse, InputState, LtlResponse, Net, NetInput, RGResponse, ReachabilityQuery,
+    ReachabilityResponse, ReplayResponse, SessionId, SimulationResponse, SimulationSession,
+    SimulationSettings, StateEquationResponse, StructuralResponse,
+};
+use crate::simulator::{SimulationLimit, SimulationOptions};
 
 mod common;
+mod expression;
 mod model_checking;
 mod simulator;
+mod structural;
 
 fn main() {
     tauri::Builder::default()
@@ -13,7 +20,15 @@ fn main() {
             simulate_start,
             simulate_start_step,
             simulate_continue,
-            check_properties
+            simulate_list,
+            simulate_drop,
+            replay_trace,
+            check_properties,
+            check_state_equation,
+            query_reachable,
+            check_ctl,
+            check_ltl,
+            analyze_structure
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -21,38 +36,119 @@ fn main() {
 
 #[tauri::command]
 fn simulate_start(
+    session: Option<SessionId>,
     marking: InputState,
-    transition_inputs: InputMatrix,
-    transition_outputs: InputMatrix,
-    update_time: i16,
+    net: NetInput,
+    update_time: Option<i16>,
+    max_steps: Option<usize>,
+    settings: Option<SimulationSettings>,
 ) -> Result<SimulationResponse, String> {
+    let net = net.into_net()?;
+    let options = simulation_options(settings.unwrap_or_default(), &net)?;
     return simulator::start_simulation(
+        session,
         marking,
-        transition_inputs,
-        transition_outputs,
-        update_time as u128,
+        net,
+        SimulationLimit::new(update_time.map(|ms| ms as u128), max_steps),
+        options,
     );
 }
 
 #[tauri::command]
 fn simulate_start_step(
+    session: Option<SessionId>,
     marking: InputState,
-    transition_inputs: InputMatrix,
-    transition_outputs: InputMatrix,
+    net: NetInput,
+    settings: Option<SimulationSettings>,
 ) -> Result<SimulationResponse, String> {
-    return simulator::start_simulation_step(marking, transition_inputs, transition_outputs);
+    let net = net.into_net()?;
+    let options = simulation_options(settings.unwrap_or_default(), &net)?;
+    return simulator::start_simulation_step(session, marking, net, options);
 }
 
 #[tauri::command]
-fn simulate_continue(update_time: i16) -> Result<SimulationResponse, String> {
-    return simulator::continue_simulation(update_time as u128);
+fn simulate_continue(
+    session: SessionId,
+    update_time: Option<i16>,
+    max_steps: Option<usize>,
+) -> Result<SimulationResponse, String> {
+    return simulator::continue_simulation(
+        session,
+        SimulationLimit::new(update_time.map(|ms| ms as u128), max_steps),
+    );
+}
+
+#[tauri::command]
+fn simulate_list() -> Result<Vec<SimulationSession>, String> {
+    return simulator::list_simulations();
+}
+
+#[tauri::command]
+fn simulate_drop(session: SessionId) -> Result<bool, String> {
+    return simulator::drop_simulation(session);
+}
+
+#[tauri::command]
+fn replay_trace(
+    marking: InputState,
+    net: NetInput,
+    transitions: InputState,
+) -> Result<ReplayResponse, String> {
+    return simulator::replay_trace(marking, net.into_net()?, transitions);
+}
+
+#[tauri::command]
+fn check_properties(marking: InputState, net: NetInput) -> Result<RGResponse, String> {
+    return model_checking::check_properties(marking, net.into_net()?);
 }
 
 #[tauri::command]
-fn check_properties(
+fn check_state_equation(
     marking: InputState,
-    transition_inputs: InputMatrix,
-    transition_outputs: InputMatrix,
-) -> Result<RGResponse, String> {
-    return model_checking::check_properties(marking, transition_inputs, transition_outputs);
+    target: InputState,
+    net: NetInput,
+) -> Result<StateEquationResponse, String> {
+    return model_checking::check_state_equation(marking, target, net.into_net()?);
+}
+
+#[tauri::command]
+fn query_reachable(
+    marking: InputState,
+    query: ReachabilityQuery,
+    net: NetInput,
+) -> Result<ReachabilityResponse, String> {
+    return model_checking::query_reachable(marking, query, net.into_net()?);
+}
+
+#[tauri::command]
+fn check_ctl(marking: InputState, formula: String, net: NetInput) -> Result<CtlResponse, String> {
+    return model_checking::check_ctl(marking, formula, net.into_net()?);
+}
+
+#[tauri::command]
+fn check_ltl(marking: InputState, formula: String, net: NetInput) -> Result<LtlResponse, String> {
+    return model_checking::check_ltl(marking, formula, net.into_net()?);
+}
+
+#[tauri::command]
+fn analyze_structure(marking: InputState, net: NetInput) -> Result<StructuralResponse, String> {
+    return structural::analyze_structure(marking, net.into_net()?);
+}
+
+fn simulation_options(
+    settings: SimulationSettings,
+    net: &Net,
+) -> Result<SimulationOptions, String> {
+    let stop_condition = settings
+        .stop_condition
+        .map(|text| expression::parse_condition(&text, net))
+        .transpose()?;
+    return Ok(SimulationOptions::new(
+        settings.seed,
+        settings.trace,
+        settings.stochastic,
+        settings.timed,
+    )
+    .with_policy(settings.conflict_policy.unwrap_or_default())
+    .with_stop_condition(stop_condition));
 }
@@ -1,26 +1,286 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Instant;
+
+use ndarray::arr1;
 use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
 use petgraph::Direction;
-use std::collections::HashSet;
 
-use crate::common::{ReachabilityGraph, State};
+use crate::common::*;
+
+/// The number of tokens that stands in for ω while firing. It is large enough to enable any arc.
+const OMEGA_TOKENS: i16 = i16::MAX / 2;
+
+/// Creates the Karp–Miller coverability graph of the net.
+///
+/// Whenever a new marking strictly covers one of its ancestors, the firing sequence from the ancestor can be repeated
+/// to pump the growing places arbitrarily high, so they are set to ω. Places with ω keep it in all successors.
+/// Places that are not monotonic are never accelerated, see [covering_witness]. If such a place grows without bound,
+/// the graph can not be completed.
+pub(super) fn create_cg(marking: InputState, net: &Net) -> Result<RGResult, String> {
+    if net.priorities.is_some() {
+        return Err("❌The coverability graph does not support transition priorities!".to_string());
+    }
+    let start_time_cg = Instant::now();
+
+    let non_monotonic_places = net.non_monotonic_places();
+    let state_vec = arr1(&marking);
+    let mut queue: Vec<NodeIndex> = Vec::new();
+    let mut graph = ReachabilityGraph::new();
+    let mut all_states_rev: HashMap<State, NodeIndex> = HashMap::new();
+
+    let mut dead_nodes = Vec::new();
+
+    let start_node = graph.add_node(state_vec.clone());
+    all_states_rev.insert(state_vec, start_node);
+    queue.push(start_node);
 
-pub(super) fn is_covering(new_node: &NodeIndex, graph: &ReachabilityGraph, pseudo: bool) -> bool {
-    let new_node_weight = graph.node_weight(*new_node).unwrap();
+    while let Some(cur_state_idx) = queue.pop() {
+        let cur_state = graph.node_weight(cur_state_idx).cloned().unwrap();
+        let active = find_active_transitions(&with_omega_tokens(&cur_state), net);
 
-    if pseudo {
-        return new_node_weight.iter().any(|&a| a > 2048);
+        if active.is_empty() {
+            dead_nodes.push(cur_state_idx);
+        }
+
+        for inx in active {
+            let mut new_state = fire_omega(&cur_state, net, inx as usize);
+            accelerate(&mut new_state, cur_state_idx, &graph, &non_monotonic_places);
+            if new_state
+                .iter()
+                .any(|&tokens| tokens != OMEGA && tokens > 30000)
+            {
+                return Err(
+                    "❌A place that can not be accelerated to ω grows without bound!".to_string(),
+                );
+            }
+
+            match all_states_rev.get(&new_state) {
+                None => {
+                    let new_state_idx = graph.add_node(new_state.clone());
+                    graph.add_edge(cur_state_idx, new_state_idx, inx);
+                    all_states_rev.insert(new_state, new_state_idx);
+                    queue.push(new_state_idx);
+                }
+                Some(&existing_node_index) => {
+                    graph.add_edge(cur_state_idx, existing_node_index, inx);
+                }
+            };
+        }
     }
 
+    println!(
+        "CG with {:?} states and {} edges took {}ms",
+        graph.node_count(),
+        graph.edge_count(),
+        start_time_cg.elapsed().as_millis()
+    );
+
+    return Ok(RGResult {
+        rg: graph,
+        dead_nodes,
+    });
+}
+
+/// Searches the shortest firing sequence from the start to the node for a marking that strictly covers an earlier
+/// marking of the sequence. The firings in between can be repeated, which proves that the net is unbounded.
+pub(super) fn find_unbounded_witness(
+    graph: &ReachabilityGraph,
+    start: NodeIndex,
+    node: NodeIndex,
+    non_monotonic_places: &[bool],
+) -> Option<UnboundedWitness> {
+    let (path, firing_sequence) = shortest_path(graph, start, node)?;
+    return (1..path.len()).find_map(|covering_step| {
+        covering_witness(
+            graph,
+            &path[..=covering_step],
+            &firing_sequence[..covering_step],
+            non_monotonic_places,
+        )
+    });
+}
+
+/// Checks whether the last marking of the path strictly covers an earlier marking of the path. The firings in between
+/// can be repeated, which proves that the net is unbounded.
+///
+/// With inhibitor arcs, the firing sequence from the ancestor can not necessarily be repeated from the covering
+/// marking, because the additional tokens might disable a transition. With reset arcs, the additional tokens might
+/// be removed again. The check therefore only accepts coverings in which none of the growing places is tested by an
+/// inhibitor arc, emptied by a reset arc or limited by a capacity.
+pub(super) fn covering_witness(
+    graph: &ReachabilityGraph,
+    path: &[NodeIndex],
+    firing_sequence: &[i16],
+    non_monotonic_places: &[bool],
+) -> Option<UnboundedWitness> {
+    let (&covering_node, ancestors) = path.split_last()?;
+    let covering = graph.node_weight(covering_node).unwrap();
+    for (ancestor_step, &ancestor_node) in ancestors.iter().enumerate() {
+        let ancestor = graph.node_weight(ancestor_node).unwrap();
+        if is_strictly_greater_than(covering, ancestor)
+            && !grows_non_monotonic_place(covering, ancestor, non_monotonic_places)
+        {
+            let growing_places = (0..covering.len())
+                .filter(|&p| covering[p] > ancestor[p])
+                .collect();
+            return Some(UnboundedWitness::new(
+                firing_sequence.to_vec(),
+                ancestor_step,
+                ancestor.to_vec(),
+                covering.to_vec(),
+                growing_places,
+            ));
+        }
+    }
+
+    return None;
+}
+
+/// Returns the nodes and the fired transitions of a shortest path between the nodes.
+fn shortest_path(
+    graph: &ReachabilityGraph,
+    from: NodeIndex,
+    to: NodeIndex,
+) -> Option<(Vec<NodeIndex>, InputState)> {
+    return shortest_path_to(graph, from, |node| node == to);
+}
+
+/// Searches the graph breadth-first for the closest node that satisfies the condition and returns the nodes and the
+/// fired transitions of the path to it.
+pub(super) fn shortest_path_to(
+    graph: &ReachabilityGraph,
+    from: NodeIndex,
+    is_target: impl Fn(NodeIndex) -> bool,
+) -> Option<(Vec<NodeIndex>, InputState)> {
+    let mut reached_by: HashMap<NodeIndex, (NodeIndex, i16)> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    while let Some(node) = queue.pop_front() {
+        if is_target(node) {
+            return tree_path(&reached_by, from, node);
+        }
+        for edge in graph.edges(node) {
+            let target = edge.target();
+            if target != from && !reached_by.contains_key(&target) {
+                reached_by.insert(target, (node, *edge.weight()));
+                queue.push_back(target);
+            }
+        }
+    }
+    return None;
+}
+
+/// Searches the graph breadth-first and returns the predecessor and the fired transition that first reached each node.
+pub(super) fn shortest_path_tree(
+    graph: &ReachabilityGraph,
+    from: NodeIndex,
+) -> HashMap<NodeIndex, (NodeIndex, i16)> {
+    let mut reached_by: HashMap<NodeIndex, (NodeIndex, i16)> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    while let Some(node) = queue.pop_front() {
+        for edge in graph.edges(node) {
+            let target = edge.target();
+            if target != from && !reached_by.contains_key(&target) {
+                reached_by.insert(target, (node, *edge.weight()));
+                queue.push_back(target);
+            }
+        }
+    }
+    return reached_by;
+}
+
+/// Follows the predecessors of the shortest path tree back from the node to the root.
+pub(super) fn tree_path(
+    reached_by: &HashMap<NodeIndex, (NodeIndex, i16)>,
+    from: NodeIndex,
+    to: NodeIndex,
+) -> Option<(Vec<NodeIndex>, InputState)> {
+    let mut path = vec![to];
+    let mut firing_sequence = Vec::new();
+    let mut node = to;
+    while node != from {
+        let &(predecessor, transition) = reached_by.get(&node)?;
+        path.push(predecessor);
+        firing_sequence.push(transition);
+        node = predecessor;
+    }
+    path.reverse();
+    firing_sequence.reverse();
+    return Some((path, firing_sequence));
+}
+
+/// Checks whether the graph is a coverability graph with unbounded places.
+pub(super) fn has_omega(graph: &ReachabilityGraph) -> bool {
+    graph
+        .node_weights()
+        .any(|state| state.iter().any(|&tokens| tokens == OMEGA))
+}
+
+/// Replaces ω with a finite number of tokens, so that the marking can be used for the enabling rules of the net.
+fn with_omega_tokens(state: &State) -> State {
+    state.mapv(|tokens| {
+        if tokens == OMEGA {
+            OMEGA_TOKENS
+        } else {
+            tokens
+        }
+    })
+}
+
+fn fire_omega(state: &State, net: &Net, t: usize) -> State {
+    let mut new_state = fire_transition(&with_omega_tokens(state), net, t);
+    new_state.zip_mut_with(state, |new, &old| {
+        if old == OMEGA {
+            *new = OMEGA
+        }
+    });
+    new_state
+}
+
+/// Sets all places to ω that grow compared to a covered ancestor, until the marking covers no more ancestors.
+fn accelerate(
+    marking: &mut State,
+    parent: NodeIndex,
+    graph: &ReachabilityGraph,
+    non_monotonic_places: &[bool],
+) {
+    loop {
+        let mut accelerated = false;
+        for ancestor in covered_ancestors(marking, parent, graph, non_monotonic_places) {
+            let ancestor_weight = graph.node_weight(ancestor).unwrap();
+            marking.zip_mut_with(ancestor_weight, |tokens, &ancestor_tokens| {
+                if *tokens != OMEGA && *tokens > ancestor_tokens {
+                    *tokens = OMEGA;
+                    accelerated = true;
+                }
+            });
+        }
+        if !accelerated {
+            return;
+        }
+    }
+}
+
+/// Returns all nodes from which the start node can be reached and which are strictly covered by the marking.
+fn covered_ancestors(
+    marking: &State,
+    start: NodeIndex,
+    graph: &ReachabilityGraph,
+    non_monotonic_places: &[bool],
+) -> Vec<NodeIndex> {
+    let mut covered = Vec::new();
     let mut visited = HashSet::new();
-    let mut stack = vec![*new_node];
+    let mut stack = vec![start];
     while let Some(node) = stack.pop() {
         if !visited.insert(node) {
             continue;
         }
 
         let weight_of_node = graph.node_weight(node).unwrap();
-        if is_strictly_greater_than(new_node_weight, weight_of_node) {
-            return true;
+        if is_strictly_greater_than(marking, weight_of_node)
+            && !grows_non_monotonic_place(marking, weight_of_node, non_monotonic_places)
+        {
+            covered.push(node);
         }
 
         let predecessors = graph.neighbors_directed(node, Direction::Incoming);
@@ -29,7 +289,7 @@ pub(super) fn is_covering(new_node: &NodeIndex, graph: &ReachabilityGraph, pseud
         }
     }
 
-    return false;
+    return covered;
 }
 
 fn is_strictly_greater_than(arr1: &State, arr2: &State) -> bool {
@@ -47,3 +307,10 @@ fn is_strictly_greater_than(arr1: &State, arr2: &State) -> bool {
 
     return !found_less && found_strictly_greater;
 }
+
+fn grows_non_monotonic_place(arr1: &State, arr2: &State, non_monotonic_places: &[bool]) -> bool {
+    arr1.iter()
+        .zip(arr2.iter())
+        .zip(non_monotonic_places.iter())
+        .any(|((&a, &b), &non_monotonic)| non_monotonic && a > b)
+}
@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+
+use petgraph::graph::NodeIndex;
+use petgraph::Direction;
+
+use crate::common::{Net, ReachabilityGraph};
+use crate::expression::{Expression, Parser};
+
+/// A CTL formula, reduced to the operators EX, EU and EG that the labeling algorithm needs
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Ctl {
+    Atom(Expression),
+    Not(Box<Ctl>),
+    And(Box<Ctl>, Box<Ctl>),
+    Or(Box<Ctl>, Box<Ctl>),
+    ExistsNext(Box<Ctl>),
+    ExistsUntil(Box<Ctl>, Box<Ctl>),
+    ExistsGlobally(Box<Ctl>),
+}
+
+fn not(formula: Ctl) -> Ctl {
+    return Ctl::Not(Box::new(formula));
+}
+
+fn and(left: Ctl, right: Ctl) -> Ctl {
+    return Ctl::And(Box::new(left), Box::new(right));
+}
+
+fn exists_until(left: Ctl, right: Ctl) -> Ctl {
+    return Ctl::ExistsUntil(Box::new(left), Box::new(right));
+}
+
+/// Parses a CTL formula like `AG (p0 >= 1 | p1 >= 1)` or `EF enabled(t4)`.
+///
+/// The operators are, from the weakest binding: `->`, `|`, `&`, and the unary `!`, `EX`, `AX`, `EF`, `AF`, `EG`, `AG`.
+/// The until operators are written as `E[φ U ψ]` and `A[φ U ψ]`.
+pub(super) fn parse_ctl(text: &str, net: &Net) -> Result<Ctl, String> {
+    let mut parser = Parser::new(text, net)?;
+    let formula = parse_implication(&mut parser)?;
+    parser.expect_end()?;
+    return Ok(formula);
+}
+
+fn parse_implication(parser: &mut Parser) -> Result<Ctl, String> {
+    let left = parse_or(parser)?;
+    if parser.accept_symbol("->") {
+        let right = parse_implication(parser)?;
+        return Ok(Ctl::Or(Box::new(not(left)), Box::new(right)));
+    }
+    return Ok(left);
+}
+
+fn parse_or(parser: &mut Parser) -> Result<Ctl, String> {
+    let mut formula = parse_and(parser)?;
+    while parser.accept_symbol("|") {
+        formula = Ctl::Or(Box::new(formula), Box::new(parse_and(parser)?));
+    }
+    return Ok(formula);
+}
+
+fn parse_and(parser: &mut Parser) -> Result<Ctl, String> {
+    let mut formula = parse_unary(parser)?;
+    while parser.accept_symbol("&") {
+        formula = and(formula, parse_unary(parser)?);
+    }
+    return Ok(formula);
+}
+
+fn parse_unary(parser: &mut Parser) -> Result<Ctl, String> {
+    let always = Ctl::Atom(Expression::Constant(true));
+    if parser.accept_symbol("!") {
+        return Ok(not(parse_unary(parser)?));
+    }
+    if parser.accept_word("EX") {
+        return Ok(Ctl::ExistsNext(Box::new(parse_unary(parser)?)));
+    }
+    if parser.accept_word("AX") {
+        // AX φ = ¬EX ¬φ
+        return Ok(not(Ctl::ExistsNext(Box::new(not(parse_unary(parser)?)))));
+    }
+    if parser.accept_word("EF") {
+        // EF φ = E[true U φ]
+        return Ok(exists_until(always, parse_unary(parser)?));
+    }
+    if parser.accept_word("AF") {
+        // AF φ = ¬EG ¬φ
+        return Ok(not(Ctl::ExistsGlobally(Box::new(not(parse_unary(
+            parser,
+        )?)))));
+    }
+    if parser.accept_word("EG") {
+        return Ok(Ctl::ExistsGlobally(Box::new(parse_unary(parser)?)));
+    }
+    if parser.accept_word("AG") {
+        // AG φ = ¬E[true U ¬φ]
+        return Ok(not(exists_until(always, not(parse_unary(parser)?))));
+    }
+    if parser.accept_word("E") {
+        let (left, right) = parse_until(parser)?;
+        return Ok(exists_until(left, right));
+    }
+    if parser.accept_word("A") {
+        // A[φ U ψ] = ¬E[¬ψ U (¬φ ∧ ¬ψ)] ∧ ¬EG ¬ψ
+        let (left, right) = parse_until(parser)?;
+        let never_right = not(right.clone());
+        let blocked = exists_until(never_right.clone(), and(not(left), never_right.clone()));
+        let endless = Ctl::ExistsGlobally(Box::new(never_right));
+        return Ok(and(not(blocked), not(endless)));
+    }
+    // parentheses around a condition like `(p0 + p1) >= 2` belong to the expression, others to the formula
+    if let Some(predicate) = parser.try_parse(Parser::parse_predicate) {
+        return Ok(Ctl::Atom(predicate));
+    }
+    if parser.accept_symbol("(") {
+        let formula = parse_implication(parser)?;
+        parser.expect_symbol(")")?;
+        return Ok(formula);
+    }
+    return Ok(Ctl::Atom(parser.parse_predicate()?));
+}
+
+/// Parses the brackets of `E[φ U ψ]` or `A[φ U ψ]` after the path quantifier.
+fn parse_until(parser: &mut Parser) -> Result<(Ctl, Ctl), String> {
+    parser.expect_symbol("[")?;
+    let left = parse_implication(parser)?;
+    if !parser.accept_word("U") {
+        return Err(parser.error("'U'"));
+    }
+    let right = parse_implication(parser)?;
+    parser.expect_symbol("]")?;
+    return Ok((left, right));
+}
+
+/// Labels every node of the reachability graph with whether it satisfies the formula.
+///
+/// CTL assumes that every state has a successor, so dead markings are treated as if they had a loop to themselves.
+pub(super) fn check_ctl(formula: &Ctl, rg: &ReachabilityGraph, net: &Net) -> Vec<bool> {
+    return match formula {
+        Ctl::Atom(predicate) => rg
+            .node_weights()
+            .map(|state| predicate.holds(state, net))
+            .collect(),
+        Ctl::Not(inner) => check_ctl(inner, rg, net).iter().map(|&sat| !sat).collect(),
+        Ctl::And(left, right) => zip_with(
+            check_ctl(left, rg, net),
+            check_ctl(right, rg, net),
+            |a, b| a && b,
+        ),
+        Ctl::Or(left, right) => zip_with(
+            check_ctl(left, rg, net),
+            check_ctl(right, rg, net),
+            |a, b| a || b,
+        ),
+        Ctl::ExistsNext(inner) => {
+            let inner = check_ctl(inner, rg, net);
+            rg.node_indices()
+                .map(|node| successors(rg, node).any(|next| inner[next.index()]))
+                .collect()
+        }
+        Ctl::ExistsUntil(left, right) => {
+            exists_until_labels(check_ctl(left, rg, net), check_ctl(right, rg, net), rg)
+        }
+        Ctl::ExistsGlobally(inner) => exists_globally_labels(check_ctl(inner, rg, net), rg),
+    };
+}
+
+fn zip_with(left: Vec<bool>, right: Vec<bool>, operator: impl Fn(bool, bool) -> bool) -> Vec<bool> {
+    return left
+        .into_iter()
+        .zip(right)
+        .map(|(a, b)| operator(a, b))
+        .collect();
+}
+
+/// The successors of a node, where a dead node is its own successor
+fn successors(rg: &ReachabilityGraph, node: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+    let dead = rg
+        .neighbors_directed(node, Direction::Outgoing)
+        .next()
+        .is_none();
+    return rg
+        .neighbors_directed(node, Direction::Outgoing)
+        .chain(dead.then_some(node));
+}
+
+/// Starts with the nodes that satisfy the right formula and adds predecessors that satisfy the left one.
+fn exists_until_labels(left: Vec<bool>, right: Vec<bool>, rg: &ReachabilityGraph) -> Vec<bool> {
+    let mut labels = right;
+    let mut queue: VecDeque<NodeIndex> = rg
+        .node_indices()
+        .filter(|node| labels[node.index()])
+        .collect();
+    while let Some(node) = queue.pop_front() {
+        for previous in rg.neighbors_directed(node, Direction::Incoming) {
+            if left[previous.index()] && !labels[previous.index()] {
+                labels[previous.index()] = true;
+                queue.push_back(previous);
+            }
+        }
+    }
+    return labels;
+}
+
+/// Starts with the nodes that satisfy the formula and removes those without a successor that is still labeled.
+fn exists_globally_labels(inner: Vec<bool>, rg: &ReachabilityGraph) -> Vec<bool> {
+    let mut labels = inner;
+    let mut labeled_successors: Vec<usize> = rg
+        .node_indices()
+        .map(|node| {
+            successors(rg, node)
+                .filter(|next| labels[next.index()])
+                .count()
+        })
+        .collect();
+    let mut queue: VecDeque<NodeIndex> = rg
+        .node_indices()
+        .filter(|node| labels[node.index()] && labeled_successors[node.index()] == 0)
+        .collect();
+    while let Some(node) = queue.pop_front() {
+        if !labels[node.index()] {
+            continue;
+        }
+        labels[node.index()] = false;
+        for previous in rg.neighbors_directed(node, Direction::Incoming) {
+            labeled_successors[previous.index()] -= 1;
+            if labels[previous.index()] && labeled_successors[previous.index()] == 0 {
+                queue.push_back(previous);
+            }
+        }
+    }
+    return labels;
+}
@@ -0,0 +1,528 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::EdgeRef;
+
+use crate::common::{LassoStep, Net, ReachabilityGraph};
+use crate::expression::{Expression, Parser};
+
+/// A proposition about one position of a run, which is a marking and the transition that fires next
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Proposition {
+    State(Expression),
+    /// `fired(t4)` holds if the transition fires at this position.
+    Fired(i16),
+}
+
+/// An LTL formula, reduced to the operators X, U and R
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Ltl {
+    Proposition(Proposition),
+    Not(Box<Ltl>),
+    And(Box<Ltl>, Box<Ltl>),
+    Or(Box<Ltl>, Box<Ltl>),
+    Next(Box<Ltl>),
+    Until(Box<Ltl>, Box<Ltl>),
+    Release(Box<Ltl>, Box<Ltl>),
+}
+
+fn constant(value: bool) -> Ltl {
+    return Ltl::Proposition(Proposition::State(Expression::Constant(value)));
+}
+
+/// Parses an LTL formula like `G F fired(t2)` or `G (p0 >= 1 -> X p1 == 0)`.
+///
+/// The operators are, from the weakest binding: `->`, `|`, `&`, the binary `U` and `R`, and the unary `!`, `X`, `F`
+/// and `G`.
+pub(super) fn parse_ltl(text: &str, net: &Net) -> Result<Ltl, String> {
+    let mut parser = Parser::new(text, net)?;
+    let formula = parse_implication(&mut parser)?;
+    parser.expect_end()?;
+    return Ok(formula);
+}
+
+fn parse_implication(parser: &mut Parser) -> Result<Ltl, String> {
+    let left = parse_or(parser)?;
+    if parser.accept_symbol("->") {
+        let right = parse_implication(parser)?;
+        return Ok(Ltl::Or(Box::new(Ltl::Not(Box::new(left))), Box::new(right)));
+    }
+    return Ok(left);
+}
+
+fn parse_or(parser: &mut Parser) -> Result<Ltl, String> {
+    let mut formula = parse_and(parser)?;
+    while parser.accept_symbol("|") {
+        formula = Ltl::Or(Box::new(formula), Box::new(parse_and(parser)?));
+    }
+    return Ok(formula);
+}
+
+fn parse_and(parser: &mut Parser) -> Result<Ltl, String> {
+    let mut formula = parse_binary(parser)?;
+    while parser.accept_symbol("&") {
+        formula = Ltl::And(Box::new(formula), Box::new(parse_binary(parser)?));
+    }
+    return Ok(formula);
+}
+
+fn parse_binary(parser: &mut Parser) -> Result<Ltl, String> {
+    let left = parse_unary(parser)?;
+    if parser.accept_word("U") {
+        return Ok(Ltl::Until(Box::new(left), Box::new(parse_binary(parser)?)));
+    }
+    if parser.accept_word("R") {
+        return Ok(Ltl::Release(
+            Box::new(left),
+            Box::new(parse_binary(parser)?),
+        ));
+    }
+    return Ok(left);
+}
+
+fn parse_unary(parser: &mut Parser) -> Result<Ltl, String> {
+    if parser.accept_symbol("!") {
+        return Ok(Ltl::Not(Box::new(parse_unary(parser)?)));
+    }
+    if parser.accept_word("X") {
+        return Ok(Ltl::Next(Box::new(parse_unary(parser)?)));
+    }
+    if parser.accept_word("F") {
+        // F φ = true U φ
+        let inner = parse_unary(parser)?;
+        return Ok(Ltl::Until(Box::new(constant(true)), Box::new(inner)));
+    }
+    if parser.accept_word("G") {
+        // G φ = false R φ
+        let inner = parse_unary(parser)?;
+        return Ok(Ltl::Release(Box::new(constant(false)), Box::new(inner)));
+    }
+    if parser.accept_word("fired") {
+        return Ok(Ltl::Proposition(Proposition::Fired(
+            parser.parse_transition()?,
+        )));
+    }
+    // parentheses around a condition like `(p0 + p1) >= 2` belong to the expression, others to the formula
+    if let Some(predicate) = parser.try_parse(Parser::parse_predicate) {
+        return Ok(Ltl::Proposition(Proposition::State(predicate)));
+    }
+    if parser.accept_symbol("(") {
+        let formula = parse_implication(parser)?;
+        parser.expect_symbol(")")?;
+        return Ok(formula);
+    }
+    let predicate = parser.parse_predicate()?;
+    return Ok(Ltl::Proposition(Proposition::State(predicate)));
+}
+
+/// Pushes the negations down to the propositions.
+fn negation_normal_form(formula: &Ltl, negated: bool) -> Ltl {
+    let (left, right) = match formula {
+        Ltl::Proposition(_) if negated => return Ltl::Not(Box::new(formula.clone())),
+        Ltl::Proposition(_) => return formula.clone(),
+        Ltl::Not(inner) => return negation_normal_form(inner, !negated),
+        Ltl::Next(inner) => return Ltl::Next(Box::new(negation_normal_form(inner, negated))),
+        Ltl::And(left, right)
+        | Ltl::Or(left, right)
+        | Ltl::Until(left, right)
+        | Ltl::Release(left, right) => (
+            Box::new(negation_normal_form(left, negated)),
+            Box::new(negation_normal_form(right, negated)),
+        ),
+    };
+    // the negation swaps each binary operator with its dual
+    return match (formula, negated) {
+        (Ltl::And(..), false) | (Ltl::Or(..), true) => Ltl::And(left, right),
+        (Ltl::Or(..), false) | (Ltl::And(..), true) => Ltl::Or(left, right),
+        (Ltl::Until(..), false) | (Ltl::Release(..), true) => Ltl::Until(left, right),
+        _ => Ltl::Release(left, right),
+    };
+}
+
+/// The incoming edge of the initial automaton states
+const INIT: usize = usize::MAX;
+
+/// A state of the Büchi automaton. The subformulas in `old` hold at the position that enters the state, and the
+/// subformulas in `next` must hold at the following position.
+struct AutomatonState {
+    incoming: BTreeSet<usize>,
+    old: BTreeSet<usize>,
+    next: BTreeSet<usize>,
+}
+
+/// A generalized Büchi automaton that accepts exactly the runs satisfying a formula in negation normal form
+struct Automaton {
+    /// All subformulas, which the states refer to by their index
+    formulas: Vec<Ltl>,
+    states: Vec<AutomatonState>,
+}
+
+impl Automaton {
+    /// Builds the automaton with the tableau construction of Gerth, Peled, Vardi and Wolper.
+    fn new(formula: Ltl) -> Self {
+        let mut automaton = Automaton {
+            formulas: Vec::new(),
+            states: Vec::new(),
+        };
+        let root = automaton.intern(&formula);
+        let mut pending = vec![(
+            BTreeSet::from([INIT]),
+            BTreeSet::from([root]),
+            BTreeSet::new(),
+            BTreeSet::new(),
+        )];
+
+        while let Some((incoming, mut new, mut old, mut next)) = pending.pop() {
+            let Some(current) = new.pop_first() else {
+                match automaton
+                    .states
+                    .iter_mut()
+                    .find(|state| state.old == old && state.next == next)
+                {
+                    Some(state) => state.incoming.extend(incoming),
+                    None => {
+                        let id = automaton.states.len();
+                        pending.push((
+                            BTreeSet::from([id]),
+                            next.clone(),
+                            BTreeSet::new(),
+                            BTreeSet::new(),
+                        ));
+                        automaton.states.push(AutomatonState {
+                            incoming,
+                            old,
+                            next,
+                        });
+                    }
+                }
+                continue;
+            };
+            if !old.insert(current) {
+                pending.push((incoming, new, old, next));
+                continue;
+            }
+
+            match automaton.formulas[current].clone() {
+                Ltl::Proposition(_) | Ltl::Not(_) => pending.push((incoming, new, old, next)),
+                Ltl::Next(inner) => {
+                    next.insert(automaton.intern(&inner));
+                    pending.push((incoming, new, old, next));
+                }
+                Ltl::And(left, right) => {
+                    new.insert(automaton.intern(&left));
+                    new.insert(automaton.intern(&right));
+                    pending.push((incoming, new, old, next));
+                }
+                Ltl::Or(left, right) => {
+                    let (left, right) = (automaton.intern(&left), automaton.intern(&right));
+                    pending.push((
+                        incoming.clone(),
+                        with(&new, &[left]),
+                        old.clone(),
+                        next.clone(),
+                    ));
+                    pending.push((incoming, with(&new, &[right]), old, next));
+                }
+                Ltl::Until(left, right) => {
+                    // φ U ψ holds if ψ holds now, or φ holds now and φ U ψ next
+                    let (left, right) = (automaton.intern(&left), automaton.intern(&right));
+                    let later = with(&next, &[current]);
+                    pending.push((incoming.clone(), with(&new, &[left]), old.clone(), later));
+                    pending.push((incoming, with(&new, &[right]), old, next));
+                }
+                Ltl::Release(left, right) => {
+                    // φ R ψ holds if φ and ψ hold now, or ψ holds now and φ R ψ next
+                    let (left, right) = (automaton.intern(&left), automaton.intern(&right));
+                    let later = with(&next, &[current]);
+                    pending.push((incoming.clone(), with(&new, &[right]), old.clone(), later));
+                    pending.push((incoming, with(&new, &[left, right]), old, next));
+                }
+            }
+        }
+        return automaton;
+    }
+
+    fn intern(&mut self, formula: &Ltl) -> usize {
+        return match self.formulas.iter().position(|known| known == formula) {
+            Some(index) => index,
+            None => {
+                self.formulas.push(formula.clone());
+                self.formulas.len() - 1
+            }
+        };
+    }
+
+    /// Returns for every until subformula the states that fulfill it: either its right side holds, or it is not
+    /// required at all. An accepted run passes through each of these sets infinitely often.
+    fn acceptance_sets(&self) -> Vec<Vec<bool>> {
+        return (0..self.formulas.len())
+            .filter_map(|until| match &self.formulas[until] {
+                Ltl::Until(_, right) => {
+                    let right = self.formulas.iter().position(|known| known == &**right);
+                    let fulfilled = self
+                        .states
+                        .iter()
+                        .map(|state| {
+                            !state.old.contains(&until)
+                                || right.is_some_and(|right| state.old.contains(&right))
+                        })
+                        .collect();
+                    Some(fulfilled)
+                }
+                _ => None,
+            })
+            .collect();
+    }
+
+    /// Checks whether the propositions that the state requires hold at the position.
+    fn accepts(
+        &self,
+        state: usize,
+        position: &Position,
+        rg: &ReachabilityGraph,
+        net: &Net,
+    ) -> bool {
+        return self.states[state]
+            .old
+            .iter()
+            .all(|&formula| match &self.formulas[formula] {
+                Ltl::Proposition(proposition) => position.satisfies(proposition, rg, net),
+                Ltl::Not(inner) => match &**inner {
+                    Ltl::Proposition(proposition) => !position.satisfies(proposition, rg, net),
+                    _ => true,
+                },
+                _ => true,
+            });
+    }
+}
+
+fn with(set: &BTreeSet<usize>, elements: &[usize]) -> BTreeSet<usize> {
+    let mut set = set.clone();
+    set.extend(elements);
+    return set;
+}
+
+/// A position of a run: a marking, the transition that fires in it, and the following marking. Dead markings repeat
+/// forever without firing a transition.
+#[derive(Clone, Copy)]
+struct Position {
+    node: NodeIndex,
+    transition: Option<i16>,
+    following: NodeIndex,
+}
+
+impl Position {
+    fn satisfies(&self, proposition: &Proposition, rg: &ReachabilityGraph, net: &Net) -> bool {
+        return match proposition {
+            Proposition::State(predicate) => predicate.holds(&rg[self.node], net),
+            Proposition::Fired(t) => self.transition == Some(*t),
+        };
+    }
+}
+
+fn positions_from(rg: &ReachabilityGraph, node: NodeIndex) -> Vec<Position> {
+    let positions: Vec<Position> = rg
+        .edges(node)
+        .map(|edge| Position {
+            node,
+            transition: Some(*edge.weight()),
+            following: edge.target(),
+        })
+        .collect();
+    if positions.is_empty() {
+        return vec![Position {
+            node,
+            transition: None,
+            following: node,
+        }];
+    }
+    return positions;
+}
+
+/// The result of an LTL check: the number of product states, and a violating run if there is one
+pub(super) struct LtlResult {
+    pub(super) states: usize,
+    pub(super) counterexample: Option<(Vec<LassoStep>, Vec<LassoStep>)>,
+}
+
+/// Checks whether every run of the reachability graph satisfies the formula. The automaton of the negated formula is
+/// combined with the graph, and every accepting cycle of the product is a run that violates the formula.
+pub(super) fn check_ltl(formula: &Ltl, rg: &ReachabilityGraph, net: &Net) -> LtlResult {
+    let automaton = Automaton::new(negation_normal_form(formula, true));
+    let acceptance_sets = automaton.acceptance_sets();
+    let mut successors: Vec<Vec<usize>> = vec![Vec::new(); automaton.states.len()];
+    let mut initial: Vec<usize> = Vec::new();
+    for (id, state) in automaton.states.iter().enumerate() {
+        for &from in state.incoming.iter() {
+            match from {
+                INIT => initial.push(id),
+                from => successors[from].push(id),
+            }
+        }
+    }
+
+    // the product combines each position of the graph with the automaton state that reads it
+    let mut product = DiGraph::<(Position, usize), ()>::new();
+    let mut indices: HashMap<(NodeIndex, Option<i16>, NodeIndex, usize), NodeIndex> =
+        HashMap::new();
+    let mut queue: VecDeque<NodeIndex> = VecDeque::new();
+    let mut add = |product: &mut DiGraph<(Position, usize), ()>,
+                   queue: &mut VecDeque<NodeIndex>,
+                   position: Position,
+                   state: usize| {
+        let key = (
+            position.node,
+            position.transition,
+            position.following,
+            state,
+        );
+        return *indices.entry(key).or_insert_with(|| {
+            let index = product.add_node((position, state));
+            queue.push_back(index);
+            index
+        });
+    };
+
+    let mut initial_nodes: HashSet<NodeIndex> = HashSet::new();
+    for position in positions_from(rg, NodeIndex::new(0)) {
+        for &state in initial.iter() {
+            if automaton.accepts(state, &position, rg, net) {
+                initial_nodes.insert(add(&mut product, &mut queue, position, state));
+            }
+        }
+    }
+    while let Some(index) = queue.pop_front() {
+        let (position, state) = product[index];
+        for next_position in positions_from(rg, position.following) {
+            for &next_state in successors[state].iter() {
+                if automaton.accepts(next_state, &next_position, rg, net) {
+                    let next = add(&mut product, &mut queue, next_position, next_state);
+                    product.update_edge(index, next, ());
+                }
+            }
+        }
+    }
+
+    let counterexample = tarjan_scc(&product)
+        .into_iter()
+        .find(|scc| {
+            let cyclic = scc.len() > 1 || product.contains_edge(scc[0], scc[0]);
+            cyclic
+                && acceptance_sets
+                    .iter()
+                    .all(|set| scc.iter().any(|&index| set[product[index].1]))
+        })
+        .map(|scc| find_lasso(&product, &initial_nodes, &scc, &acceptance_sets, rg));
+
+    return LtlResult {
+        states: product.node_count(),
+        counterexample,
+    };
+}
+
+/// Builds a run that enters the component and then cycles through every acceptance set inside it forever.
+fn find_lasso(
+    product: &DiGraph<(Position, usize), ()>,
+    initial_nodes: &HashSet<NodeIndex>,
+    scc: &[NodeIndex],
+    acceptance_sets: &[Vec<bool>],
+    rg: &ReachabilityGraph,
+) -> (Vec<LassoStep>, Vec<LassoStep>) {
+    let component: HashSet<NodeIndex> = scc.iter().copied().collect();
+    let stem = shortest_path(product, initial_nodes.iter().copied().collect(), |index| {
+        component.contains(&index)
+    })
+    .unwrap();
+    let entry = *stem.last().unwrap();
+
+    let mut cycle = vec![entry];
+    for set in acceptance_sets {
+        let current = *cycle.last().unwrap();
+        if set[product[current].1] {
+            continue;
+        }
+        let inside = successors_within(product, &component, current);
+        let path = shortest_path(product, inside, |index| {
+            component.contains(&index) && set[product[index].1]
+        })
+        .unwrap();
+        cycle.extend(path);
+    }
+    let inside = successors_within(product, &component, *cycle.last().unwrap());
+    let back = shortest_path(product, inside, |index| index == entry).unwrap();
+    cycle.extend(&back[..back.len() - 1]);
+
+    // the automaton may need several rounds for what is a single round in the graph, like a dead marking, and the
+    // stem may already run along the cycle
+    let same = |a: NodeIndex, b: NodeIndex| {
+        let (a, b) = (product[a].0, product[b].0);
+        a.node == b.node && a.transition == b.transition
+    };
+    let period = (1..=cycle.len())
+        .find(|&period| {
+            cycle.len() % period == 0
+                && (period..cycle.len()).all(|i| same(cycle[i], cycle[i % period]))
+        })
+        .unwrap();
+    cycle.truncate(period);
+    let mut stem = stem[..stem.len() - 1].to_vec();
+    while stem
+        .last()
+        .is_some_and(|&last| same(last, *cycle.last().unwrap()))
+    {
+        stem.pop();
+        cycle.rotate_right(1);
+    }
+
+    let to_steps = |nodes: &[NodeIndex]| -> Vec<LassoStep> {
+        nodes
+            .iter()
+            .map(|&index| {
+                let position = product[index].0;
+                LassoStep::new(rg[position.node].to_vec(), position.transition)
+            })
+            .collect()
+    };
+    return (to_steps(&stem), to_steps(&cycle));
+}
+
+fn successors_within(
+    product: &DiGraph<(Position, usize), ()>,
+    component: &HashSet<NodeIndex>,
+    index: NodeIndex,
+) -> Vec<NodeIndex> {
+    return product
+        .neighbors(index)
+        .filter(|next| component.contains(next))
+        .collect();
+}
+
+/// Searches breadth-first from the start nodes for the closest node that satisfies the condition.
+fn shortest_path(
+    product: &DiGraph<(Position, usize), ()>,
+    start: Vec<NodeIndex>,
+    is_target: impl Fn(NodeIndex) -> bool,
+) -> Option<Vec<NodeIndex>> {
+    let mut reached_by: HashMap<NodeIndex, Option<NodeIndex>> =
+        start.iter().map(|&index| (index, None)).collect();
+    let mut queue: VecDeque<NodeIndex> = start.into_iter().collect();
+    while let Some(index) = queue.pop_front() {
+        if is_target(index) {
+            let mut path = vec![index];
+            while let Some(&Some(previous)) = reached_by.get(path.last().unwrap()) {
+                path.push(previous);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for next in product.neighbors(index) {
+            if let Entry::Vacant(entry) = reached_by.entry(next) {
+                entry.insert(Some(index));
+                queue.push_back(next);
+            }
+        }
+    }
+    return None;
+}
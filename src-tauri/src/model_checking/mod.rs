@@ -1,23 +1,61 @@
 use std::time::Instant;
 
 use crate::common::*;
+use crate::model_checking::reachability::RGError;
 
 mod coverability;
+mod ctl;
+mod ltl;
 mod mod_test;
 mod properties;
+mod query;
 mod reachability;
+mod state_equation;
 
-pub fn check_properties(
-    marking: InputState,
-    transition_inputs: InputMatrix,
-    transition_outputs: InputMatrix,
-) -> Result<RGResponse, String> {
-    let t = transition_inputs.len();
+pub fn check_properties(marking: InputState, net: Net) -> Result<RGResponse, String> {
+    net.check_marking(&marking)?;
+    let t = net.transition_count();
     let start_time_rg = Instant::now();
-    let rg_result = reachability::create_rg(marking, transition_inputs, transition_outputs);
+    let mut witness = None;
+    let rg_result = match reachability::create_rg(marking.clone(), &net) {
+        Ok(result) => Ok(result),
+        Err(RGError::Unbounded(unbounded_witness)) => {
+            println!("RG is unbounded, creating the coverability graph instead");
+            witness = Some(unbounded_witness);
+            coverability::create_cg(marking, &net)
+        }
+        Err(RGError::TooLarge) if net.priorities.is_some() => {
+            return Err("❌The reachability graph is too large and the coverability graph does not support \
+                        transition priorities, so the boundedness of the net is unknown!"
+                .to_string());
+        }
+        Err(error) => return Err(error.message()),
+    };
     let end_time_rg = Instant::now();
 
     return match rg_result {
+        Ok(result) if coverability::has_omega(&result.rg) => {
+            let bounded_vec = properties::get_bounded_vector(&result.rg);
+            let dot_graph = reachability::create_dot_graph(&result.rg, &result.dead_nodes);
+            let total_cg = end_time_rg - start_time_rg;
+            println!(
+                "---∞ CG with {} nodes took {}ms ---",
+                result.rg.node_count(),
+                total_cg.as_millis()
+            );
+
+            return Ok(RGResponse::coverability(
+                &result,
+                bounded_vec,
+                dot_graph,
+                format!(
+                    "Graph is unbounded. Coverability graph took {}ms",
+                    total_cg.as_millis()
+                ),
+            )
+            .with_unbounded_witness(witness)
+            .with_deadlocks(reachability::find_deadlocks(&result)));
+        }
         Ok(result) => {
             let rg = &result.rg;
 
@@ -26,7 +64,7 @@ pub fn check_properties(
             let end_time_properties = Instant::now();
 
             let start_time_visualization = Instant::now();
-            let dot_graph = reachability::create_dot_graph(rg);
+            let dot_graph = reachability::create_dot_graph(rg, &result.dead_nodes);
             let end_time_visualization = Instant::now();
 
             let total = end_time_visualization - start_time_rg;
@@ -55,10 +93,157 @@ pub fn check_properties(
             return Ok(RGResponse::success(
                 &rg,
                 &rg_properties,
+                net.capacity_reached(&rg_properties.bounded_vec),
                 dot_graph,
                 time_string,
+            )
+            .with_deadlocks(reachability::find_deadlocks(&result)));
+        }
+        Err(_) => Ok(RGResponse::unbounded().with_unbounded_witness(witness)),
+    };
+}
+
+/// Checks whether the target marking solves the state equation before exploring the state space. Without a solution,
+/// the target is unreachable. Otherwise, the smallest solution guides the search for a firing sequence to the target.
+pub fn check_state_equation(
+    marking: InputState,
+    target: InputState,
+    net: Net,
+) -> Result<StateEquationResponse, String> {
+    net.check_marking(&marking)?;
+    net.check_marking(&target)?;
+    if target.len() != marking.len() {
+        return Err("❌The target does not match the places of the net!".to_string());
+    }
+    if net.t_reset.is_some() {
+        return Err("❌The state equation does not hold for nets with reset arcs!".to_string());
+    }
+    let start_time = Instant::now();
+
+    let Some(parikh_vector) = state_equation::solve_state_equation(&marking, &target, &net)? else {
+        return Ok(StateEquationResponse::new(
+            Some(false),
+            None,
+            None,
+            format!(
+                "The state equation has no solution, so the target is unreachable. Took {}ms",
+                start_time.elapsed().as_millis()
+            ),
+        ));
+    };
+    let firing_sequence = state_equation::find_firing_sequence(&marking, &parikh_vector, &net);
+    let (reachable, message) = match firing_sequence {
+        Some(_) => (Some(true), "The target is reachable"),
+        // the search is bounded and other solutions might be realizable, so reachability stays open
+        None => (
+            None,
+            "The state equation has a solution, but no firing sequence for the minimal solution was found",
+        ),
+    };
+
+    return Ok(StateEquationResponse::new(
+        reachable,
+        Some(parikh_vector),
+        firing_sequence,
+        format!("{}. Took {}ms", message, start_time.elapsed().as_millis()),
+    ));
+}
+
+/// Searches the state space for a marking that satisfies the query and returns the shortest firing sequence to it.
+pub fn query_reachable(
+    marking: InputState,
+    query: ReachabilityQuery,
+    net: Net,
+) -> Result<ReachabilityResponse, String> {
+    net.check_marking(&marking)?;
+    let condition = query::compile_query(&query, &marking, &net)?;
+    let start_time = Instant::now();
+
+    // the state equation proves many target markings unreachable without exploring the state space
+    if let ReachabilityQuery::Marking { marking: target } = &query {
+        if net.t_reset.is_none()
+            && state_equation::solve_state_equation(&marking, target, &net)?.is_none()
+        {
+            return Ok(ReachabilityResponse::new(
+                Some(false),
+                None,
+                None,
+                0,
+                format!(
+                    "The state equation has no solution, so the target is unreachable. Took {}ms",
+                    start_time.elapsed().as_millis()
+                ),
             ));
         }
-        Err(_) => Ok(RGResponse::unbounded()),
+    }
+
+    let result = query::search(&marking, &condition, &net);
+    let (reachable, message) = match (&result.witness, result.exhausted) {
+        (Some(_), _) => (Some(true), "Found a marking that satisfies the query"),
+        (None, true) => (Some(false), "No reachable marking satisfies the query"),
+        (None, false) => (None, "Stopped the search before exploring all markings"),
     };
+    let (firing_sequence, markings) = result.witness.unzip();
+
+    return Ok(ReachabilityResponse::new(
+        reachable,
+        firing_sequence,
+        markings,
+        result.states,
+        format!(
+            "{} after {} states. Took {}ms",
+            message,
+            result.states,
+            start_time.elapsed().as_millis()
+        ),
+    ));
+}
+
+/// Checks a CTL formula on the reachability graph and returns the markings that satisfy it.
+pub fn check_ctl(marking: InputState, formula: String, net: Net) -> Result<CtlResponse, String> {
+    net.check_marking(&marking)?;
+    let formula = ctl::parse_ctl(&formula, &net)?;
+    let start_time = Instant::now();
+    let result = reachability::create_rg(marking, &net).map_err(|error| error.message())?;
+
+    let labels = ctl::check_ctl(&formula, &result.rg, &net);
+    let satisfying_states = result
+        .rg
+        .node_indices()
+        .filter(|node| labels[node.index()])
+        .map(|node| result.rg[node].to_vec())
+        .collect();
+
+    return Ok(CtlResponse::new(
+        labels[0],
+        satisfying_states,
+        result.rg.node_count(),
+        format!(
+            "Checking the formula on {} states took {}ms",
+            result.rg.node_count(),
+            start_time.elapsed().as_millis()
+        ),
+    ));
+}
+
+/// Checks an LTL formula on all runs of the reachability graph and returns a lasso-shaped counterexample if it fails.
+pub fn check_ltl(marking: InputState, formula: String, net: Net) -> Result<LtlResponse, String> {
+    net.check_marking(&marking)?;
+    let formula = ltl::parse_ltl(&formula, &net)?;
+    let start_time = Instant::now();
+    let result = reachability::create_rg(marking, &net).map_err(|error| error.message())?;
+
+    let ltl_result = ltl::check_ltl(&formula, &result.rg, &net);
+    return Ok(LtlResponse::new(
+        ltl_result.counterexample.is_none(),
+        ltl_result
+            .counterexample
+            .map(|(stem, cycle)| Lasso::new(stem, cycle)),
+        ltl_result.states,
+        format!(
+            "Checking the formula on {} product states took {}ms",
+            ltl_result.states,
+            start_time.elapsed().as_millis()
+        ),
+    ));
 }
@@ -1,8 +1,18 @@
 #[cfg(test)]
 mod tests {
-    use crate::common::RGResponse;
-    use crate::model_checking::check_properties;
-    use crate::model_checking::mod_test::ExpectedRGResponse;
+    use crate::common::{
+        Comparison, CtlResponse, InputMatrix, InputState, LtlResponse, Net, RGResponse,
+        ReachabilityQuery, ReachabilityResponse, StateEquationResponse, TokenCondition,
+    };
+
+    fn check_properties(
+        marking: InputState,
+        transition_inputs: InputMatrix,
+        transition_outputs: InputMatrix,
+    ) -> Result<RGResponse, String> {
+        let net = Net::new(&transition_inputs, &transition_outputs)?;
+        crate::model_checking::check_properties(marking, net)
+    }
 
     #[test]
     fn single_firing() {
@@ -25,19 +35,16 @@ mod tests {
     #[test]
     fn unbounded() {
         // Unbounded net ███──>(1)
-        let result = check_properties(vec![1], vec![vec![0]], vec![vec![1]]);
+        let result = check_properties(vec![1], vec![vec![0]], vec![vec![1]]).unwrap();
 
-        let expected = ExpectedRGResponse {
-            states: 0,
-            edges: 0,
-            reversible: false,
-            liveness: false,
-            bounded: -1,
-            bounded_vec: vec![],
-            has_deadlock: false,
-        };
-
-        assert_result(result, expected);
+        assert_eq!(result.states, 2);
+        assert_eq!(result.edges, 2);
+        assert_eq!(result.bounded, -1);
+        assert_eq!(result.bounded_vec, vec![-1]);
+        // the coverability graph decides neither liveness nor reversibility, and can not rule out deadlocks
+        assert_eq!(result.reversible, None);
+        assert_eq!(result.liveness, None);
+        assert_eq!(result.has_deadlock, None);
     }
 
     #[test]
@@ -162,6 +169,815 @@ mod tests {
         assert_result(result, expected);
     }
 
+    #[test]
+    fn inhibitor_zero_test() {
+        // The second transition may only fire once the first place is empty
+        // (2)──>███──>(0)──>███──>(0)
+        //  └─────────────────o
+
+        let net = Net::new(
+            &vec![vec![1, 0, 0], vec![0, 1, 0]],
+            &vec![vec![0, 1, 0], vec![0, 0, 1]],
+        )
+        .and_then(|net| net.with_inhibitors(Some(vec![vec![0, 0, 0], vec![1, 0, 0]])));
+        let result = crate::model_checking::check_properties(vec![2, 0, 0], net.unwrap());
+
+        let expected = ExpectedRGResponse {
+            states: 5,
+            edges: 4,
+            reversible: false,
+            liveness: false,
+            bounded: 2,
+            bounded_vec: vec![2, 2, 2],
+            has_deadlock: true,
+        };
+
+        assert_result(result, expected);
+    }
+
+    #[test]
+    fn inhibitor_bounds_producer() {
+        // A producer that is inhibited once its output place holds two tokens
+        //  ███──>(0)
+        //   o─────┘
+        let net = Net::new(&vec![vec![0]], &vec![vec![1]])
+            .and_then(|net| net.with_inhibitors(Some(vec![vec![2]])));
+        let result = crate::model_checking::check_properties(vec![0], net.unwrap());
+
+        let expected = ExpectedRGResponse {
+            states: 3,
+            edges: 2,
+            reversible: false,
+            liveness: false,
+            bounded: 2,
+            bounded_vec: vec![2],
+            has_deadlock: true,
+        };
+
+        assert_result(result, expected);
+    }
+
+    #[test]
+    fn reset_and_read_arcs() {
+        // The second transition needs a token in the second place without consuming it and empties the first two places
+        // (2)──>███──>(0)──>███──>(0)
+        //  └───────────────»┘
+        let net = Net::new(
+            &vec![vec![1, 0, 0], vec![0, 0, 0]],
+            &vec![vec![0, 1, 0], vec![0, 0, 1]],
+        )
+        .and_then(|net| net.with_resets(Some(vec![vec![0, 0, 0], vec![1, 1, 0]])))
+        .and_then(|net| net.with_reads(Some(vec![vec![0, 0, 0], vec![0, 1, 0]])));
+        let result = crate::model_checking::check_properties(vec![2, 0, 0], net.unwrap());
+
+        let expected = ExpectedRGResponse {
+            states: 4,
+            edges: 4,
+            reversible: false,
+            liveness: false,
+            bounded: 2,
+            bounded_vec: vec![2, 2, 1],
+            has_deadlock: true,
+        };
+
+        assert_result(result, expected);
+    }
+
+    #[test]
+    fn reset_prevents_false_unboundedness() {
+        // The transition empties its output place before adding a token, so it may fire forever without growing it
+        //  ███──>(0)
+        //   »─────┘
+        let net = Net::new(&vec![vec![0]], &vec![vec![1]])
+            .and_then(|net| net.with_resets(Some(vec![vec![1]])));
+        let result = crate::model_checking::check_properties(vec![0], net.unwrap());
+
+        let expected = ExpectedRGResponse {
+            states: 2,
+            edges: 2,
+            reversible: false,
+            liveness: true,
+            bounded: 1,
+            bounded_vec: vec![1],
+            has_deadlock: false,
+        };
+
+        assert_result(result, expected);
+    }
+
+    #[test]
+    fn capacity_bounds_producer() {
+        // A producer whose output place can hold at most three tokens, and a consumer of a second place
+        //  ███──>(0)[3]   (1)──>███
+        let net = Net::new(&vec![vec![0, 0], vec![0, 1]], &vec![vec![1, 0], vec![0, 0]])
+            .and_then(|net| net.with_capacities(Some(vec![Some(3), None])))
+            .unwrap();
+        let result = crate::model_checking::check_properties(vec![0, 1], net).unwrap();
+
+        assert_eq!(result.states, 8);
+        assert_eq!(result.bounded_vec, vec![3, 1]);
+        assert_eq!(result.capacity_reached, vec![true, false]);
+        assert_eq!(result.has_deadlock, Some(true));
+    }
+
+    #[test]
+    fn marking_must_respect_capacities() {
+        let net = Net::new(&vec![vec![1]], &vec![vec![1]])
+            .and_then(|net| net.with_capacities(Some(vec![Some(1)])))
+            .unwrap();
+
+        assert!(crate::model_checking::check_properties(vec![2], net).is_err());
+    }
+
+    #[test]
+    fn priorities_prune_reachability_graph() {
+        // Both transitions compete for the token, but only the second one may fire
+        //  ┌──>███──>(0)
+        // (1)
+        //  └──>███──>(0)
+        let net = Net::new(
+            &vec![vec![1, 0, 0], vec![1, 0, 0]],
+            &vec![vec![0, 1, 0], vec![0, 0, 1]],
+        )
+        .and_then(|net| net.with_priorities(Some(vec![0, 1])));
+        let result = crate::model_checking::check_properties(vec![1, 0, 0], net.unwrap());
+
+        let expected = ExpectedRGResponse {
+            states: 2,
+            edges: 1,
+            reversible: false,
+            liveness: false,
+            bounded: 1,
+            bounded_vec: vec![1, 0, 1],
+            has_deadlock: true,
+        };
+
+        assert_result(result, expected);
+    }
+
+    #[test]
+    fn coverability_graph_reports_unbounded_places() {
+        // The first place is pumped by the loop, the third place stays bounded
+        //   ┌──>███──>(0)
+        //  (1)<──┘
+        //   └──>███──>(0)
+        let result = check_properties(
+            vec![0, 1, 0],
+            vec![vec![0, 1, 0], vec![0, 1, 0]],
+            vec![vec![1, 1, 0], vec![0, 0, 1]],
+        )
+        .unwrap();
+
+        assert_eq!(result.bounded, -1);
+        assert_eq!(result.bounded_vec, vec![-1, 1, 1]);
+        assert_eq!(result.states, 4);
+        assert_eq!(result.has_deadlock, Some(true));
+        assert!(result.dot_graph.contains("[ω, 0, 1]"));
+    }
+
+    #[test]
+    fn unbounded_witness_explains_pumping() {
+        // The token moves once, then the loop on the second place pumps the third place
+        // (1)──>███──>(0)──>███──>(0)
+        //             └───<───┘
+        let result = check_properties(
+            vec![1, 0, 0],
+            vec![vec![1, 0, 0], vec![0, 1, 0]],
+            vec![vec![0, 1, 0], vec![0, 1, 1]],
+        )
+        .unwrap();
+        let witness = result.unbounded_witness.unwrap();
+
+        assert_eq!(witness.firing_sequence, vec![0, 1]);
+        assert_eq!(witness.ancestor_step, 1);
+        assert_eq!(witness.ancestor, vec![0, 1, 0]);
+        assert_eq!(witness.covering, vec![0, 1, 1]);
+        assert_eq!(witness.growing_places, vec![2]);
+    }
+
+    #[test]
+    fn large_bounded_net_is_not_unbounded() {
+        // Thousands of tokens, but no marking covers an earlier one, so the net is bounded
+        // (3000)─1000─>███─1000─>(0)
+        let result =
+            check_properties(vec![3000, 0], vec![vec![1000, 0]], vec![vec![0, 1000]]).unwrap();
+
+        assert_eq!(result.bounded, 3000);
+        assert_eq!(result.bounded_vec, vec![3000, 3000]);
+        assert_eq!(result.states, 4);
+        assert_eq!(result.has_deadlock, Some(true));
+    }
+
+    #[test]
+    fn too_many_tokens_are_not_unbounded() {
+        // The inhibitor arc stops the producer at 31999 tokens, before the marking proves anything
+        // ███──>(0)
+        //  o─────┘
+        let net = Net::new(&vec![vec![0]], &vec![vec![1]])
+            .and_then(|net| net.with_inhibitors(Some(vec![vec![32000]])));
+        let error = crate::model_checking::check_properties(vec![0], net.unwrap())
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            error,
+            "❌The reachability graph has more than 1000000 states or more than 30000 tokens in a place!"
+        );
+    }
+
+    #[test]
+    fn priorities_leave_boundedness_unknown() {
+        // The producer has priority, so more tokens never prove that the place grows without bound
+        // ███──>(0)──>███
+        let net = Net::new(&vec![vec![0], vec![1]], &vec![vec![1], vec![0]])
+            .and_then(|net| net.with_priorities(Some(vec![1, 0])));
+        let error = crate::model_checking::check_properties(vec![0], net.unwrap())
+            .err()
+            .unwrap();
+
+        assert!(
+            error.contains("does not support transition priorities"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn deadlocks_with_shortest_firing_sequences() {
+        // A choice between two transitions that each end in a different dead marking
+        //  ┌──>███──>(0)──>███──>(0)
+        // (1)
+        //  └──>███──>(0)
+        let result = check_properties(
+            vec![1, 0, 0, 0],
+            vec![vec![1, 0, 0, 0], vec![0, 1, 0, 0], vec![1, 0, 0, 0]],
+            vec![vec![0, 1, 0, 0], vec![0, 0, 1, 0], vec![0, 0, 0, 1]],
+        )
+        .unwrap();
+        let deadlocks: Vec<(InputState, InputState)> = result
+            .deadlocks
+            .iter()
+            .map(|deadlock| (deadlock.marking.clone(), deadlock.firing_sequence.clone()))
+            .collect();
+
+        assert_eq!(result.has_deadlock, Some(true));
+        assert_eq!(
+            deadlocks,
+            vec![(vec![0, 0, 0, 1], vec![2]), (vec![0, 0, 1, 0], vec![0, 1])]
+        );
+        assert_eq!(result.dot_graph.matches("#F44336").count(), 2);
+    }
+
+    #[test]
+    fn live_net_has_no_deadlocks() {
+        // Circle   ┌──>███─>(0)
+        //         (1)<─███<──┘
+        let result = check_properties(
+            vec![1, 0],
+            vec![vec![0, 1], vec![1, 0]],
+            vec![vec![1, 0], vec![0, 1]],
+        )
+        .unwrap();
+
+        assert!(result.deadlocks.is_empty());
+        assert!(!result.dot_graph.contains("#F44336"));
+    }
+
+    #[test]
+    fn transition_liveness_levels() {
+        // The first place loops or moves its token to the second place, which loops forever
+        // ┌>███<┐        ┌>███<┐
+        // └─(1)──>███──>(0)───┘   (0)──>███
+        let result = check_properties(
+            vec![1, 0, 0],
+            vec![vec![1, 0, 0], vec![1, 0, 0], vec![0, 1, 0], vec![0, 0, 1]],
+            vec![vec![1, 0, 0], vec![0, 1, 0], vec![0, 1, 0], vec![0, 0, 0]],
+        )
+        .unwrap();
+        let levels: Vec<u8> = result.transition_liveness.iter().map(|t| t.level).collect();
+
+        assert_eq!(levels, vec![3, 1, 4, 0]);
+        assert_eq!(
+            result.transition_liveness[0].never_fires_from,
+            Some(vec![0, 1, 0])
+        );
+        assert_eq!(result.transition_liveness[2].never_fires_from, None);
+        assert_eq!(result.liveness, Some(false));
+    }
+
+    #[test]
+    fn transitions_before_deadlock_are_l1_live() {
+        // Simple net (1)──>███
+        let result = check_properties(vec![1], vec![vec![1]], vec![vec![0]]).unwrap();
+
+        assert_eq!(result.transition_liveness[0].level, 1);
+        assert_eq!(
+            result.transition_liveness[0].never_fires_from,
+            Some(vec![0])
+        );
+    }
+
+    #[test]
+    fn home_states_after_start_up() {
+        // A start-up transition moves the token into a circle that it never leaves
+        // (1)──>███──>(0)──>███──>(0)
+        //              └<───███<───┘
+        let result = check_properties(
+            vec![1, 0, 0],
+            vec![vec![1, 0, 0], vec![0, 1, 0], vec![0, 0, 1]],
+            vec![vec![0, 1, 0], vec![0, 0, 1], vec![0, 1, 0]],
+        )
+        .unwrap();
+        let witness = result.reversibility_witness.unwrap();
+
+        assert_eq!(result.reversible, Some(false));
+        assert_eq!(result.home_states, vec![vec![0, 0, 1], vec![0, 1, 0]]);
+        assert_eq!(witness.marking, vec![0, 1, 0]);
+        assert_eq!(witness.firing_sequence, vec![0]);
+    }
+
+    #[test]
+    fn reversible_net_has_every_marking_as_home_state() {
+        // Circle   ┌──>███─>(0)
+        //         (1)<─███<──┘
+        let result = check_properties(
+            vec![1, 0],
+            vec![vec![0, 1], vec![1, 0]],
+            vec![vec![1, 0], vec![0, 1]],
+        )
+        .unwrap();
+
+        assert_eq!(result.reversible, Some(true));
+        assert_eq!(result.home_states, vec![vec![0, 1], vec![1, 0]]);
+        assert!(result.reversibility_witness.is_none());
+    }
+
+    #[test]
+    fn separate_deadlocks_are_no_home_states() {
+        // A choice between two transitions that lead to different dead markings
+        let result = check_properties(
+            vec![1, 0, 0],
+            vec![vec![1, 0, 0], vec![1, 0, 0]],
+            vec![vec![0, 1, 0], vec![0, 0, 1]],
+        )
+        .unwrap();
+
+        assert!(result.home_states.is_empty());
+        assert_eq!(
+            result.reversibility_witness.unwrap().firing_sequence.len(),
+            1
+        );
+    }
+
+    fn check_state_equation(
+        marking: InputState,
+        target: InputState,
+        transition_inputs: InputMatrix,
+        transition_outputs: InputMatrix,
+    ) -> Result<StateEquationResponse, String> {
+        let net = Net::new(&transition_inputs, &transition_outputs)?;
+        crate::model_checking::check_state_equation(marking, target, net)
+    }
+
+    #[test]
+    fn state_equation_finds_firing_sequence() {
+        // Circle   ┌──>███─>(0)
+        //         (1)<─███<──┘
+        let result = check_state_equation(
+            vec![0, 1],
+            vec![1, 0],
+            vec![vec![0, 1], vec![1, 0]],
+            vec![vec![1, 0], vec![0, 1]],
+        )
+        .unwrap();
+
+        assert_eq!(result.reachable, Some(true));
+        assert_eq!(result.parikh_vector, Some(vec![1, 0]));
+        assert_eq!(result.firing_sequence, Some(vec![0]));
+    }
+
+    #[test]
+    fn state_equation_proves_unreachability() {
+        // The circle never holds more than one token
+        let result = check_state_equation(
+            vec![0, 1],
+            vec![1, 1],
+            vec![vec![0, 1], vec![1, 0]],
+            vec![vec![1, 0], vec![0, 1]],
+        )
+        .unwrap();
+
+        assert_eq!(result.reachable, Some(false));
+        assert_eq!(result.parikh_vector, None);
+        assert_eq!(result.firing_sequence, None);
+    }
+
+    #[test]
+    fn state_equation_guides_longer_search() {
+        // ███──>(0)──>███──>(0)
+        let result = check_state_equation(
+            vec![0, 0],
+            vec![0, 3],
+            vec![vec![0, 0], vec![1, 0]],
+            vec![vec![1, 0], vec![0, 1]],
+        )
+        .unwrap();
+
+        assert_eq!(result.reachable, Some(true));
+        assert_eq!(result.parikh_vector, Some(vec![3, 3]));
+        assert_eq!(result.firing_sequence.unwrap().len(), 6);
+    }
+
+    #[test]
+    fn state_equation_solution_without_firing_sequence() {
+        // Both transitions together produce a token in the third place, but neither can start
+        //  ┌──>███──>(0)──>███──┐
+        // (0)   └──>(0)         │
+        //  └<───────────────────┘
+        let result = check_state_equation(
+            vec![0, 0, 0],
+            vec![0, 0, 1],
+            vec![vec![0, 1, 0], vec![1, 0, 0]],
+            vec![vec![1, 0, 1], vec![0, 1, 0]],
+        )
+        .unwrap();
+
+        assert_eq!(result.reachable, None);
+        assert_eq!(result.parikh_vector, Some(vec![1, 1]));
+        assert_eq!(result.firing_sequence, None);
+        assert!(result
+            .message
+            .contains("no firing sequence for the minimal solution was found"));
+    }
+
+    #[test]
+    fn state_equation_rejects_resets() {
+        let net = Net::new(&vec![vec![1]], &vec![vec![0]])
+            .and_then(|net| net.with_resets(Some(vec![vec![1]])))
+            .unwrap();
+        let result = crate::model_checking::check_state_equation(vec![1], vec![0], net);
+
+        assert!(result.is_err());
+    }
+
+    fn query_reachable(
+        marking: InputState,
+        query: ReachabilityQuery,
+        transition_inputs: InputMatrix,
+        transition_outputs: InputMatrix,
+    ) -> Result<ReachabilityResponse, String> {
+        let net = Net::new(&transition_inputs, &transition_outputs)?;
+        crate::model_checking::query_reachable(marking, query, net)
+    }
+
+    fn at_least(place: usize, tokens: i16) -> ReachabilityQuery {
+        let condition = TokenCondition {
+            place,
+            comparison: Comparison::GreaterEqual,
+            tokens,
+        };
+        ReachabilityQuery::Tokens {
+            conditions: vec![condition],
+        }
+    }
+
+    #[test]
+    fn buffer_can_hold_three_parts() {
+        // A producer fills the buffer, a consumer empties it
+        // ███──>(0)──>███
+        let result = query_reachable(
+            vec![0],
+            at_least(0, 3),
+            vec![vec![0], vec![1]],
+            vec![vec![1], vec![0]],
+        )
+        .unwrap();
+
+        assert_eq!(result.reachable, Some(true));
+        assert_eq!(result.firing_sequence, Some(vec![0, 0, 0]));
+        assert_eq!(
+            result.markings,
+            Some(vec![vec![0], vec![1], vec![2], vec![3]])
+        );
+    }
+
+    #[test]
+    fn query_stops_before_tokens_overflow() {
+        // A source transition fills the place forever
+        // ███──>(0)
+        let never = TokenCondition {
+            place: 0,
+            comparison: Comparison::Less,
+            tokens: 0,
+        };
+        let result = query_reachable(
+            vec![0],
+            ReachabilityQuery::Tokens {
+                conditions: vec![never],
+            },
+            vec![vec![0]],
+            vec![vec![1]],
+        )
+        .unwrap();
+
+        assert_eq!(result.reachable, None);
+        assert_eq!(result.firing_sequence, None);
+    }
+
+    #[test]
+    fn query_accepts_expressions() {
+        // Two buffers that are filled one part at a time
+        // ███──>(0)   ███──>(1)
+        let query = |expression: &str| {
+            query_reachable(
+                vec![0, 0],
+                ReachabilityQuery::Expression {
+                    expression: expression.to_string(),
+                },
+                vec![vec![0, 0], vec![0, 0]],
+                vec![vec![1, 0], vec![0, 1]],
+            )
+        };
+
+        let sum = query("p0 + p1 == 3 & p0 > p1").unwrap();
+        assert_eq!(sum.reachable, Some(true));
+        assert_eq!(sum.firing_sequence, Some(vec![0, 0, 0]));
+
+        let error = query("p0 + p1").unwrap_err();
+        assert_eq!(
+            error,
+            "❌Expected a condition at position 1, but found a number!"
+        );
+    }
+
+    #[test]
+    fn query_finds_shortest_path() {
+        // Circle   ┌──>███─>(0)
+        //         (1)<─███<──┘
+        let result = query_reachable(
+            vec![1, 0],
+            ReachabilityQuery::Marking {
+                marking: vec![0, 1],
+            },
+            vec![vec![0, 1], vec![1, 0]],
+            vec![vec![1, 0], vec![0, 1]],
+        )
+        .unwrap();
+
+        assert_eq!(result.reachable, Some(true));
+        assert_eq!(result.firing_sequence, Some(vec![1]));
+        assert_eq!(result.markings, Some(vec![vec![1, 0], vec![0, 1]]));
+    }
+
+    #[test]
+    fn query_explores_all_markings() {
+        // The circle never holds two tokens in one place
+        let result = query_reachable(
+            vec![1, 0],
+            at_least(1, 2),
+            vec![vec![0, 1], vec![1, 0]],
+            vec![vec![1, 0], vec![0, 1]],
+        )
+        .unwrap();
+
+        assert_eq!(result.reachable, Some(false));
+        assert_eq!(result.firing_sequence, None);
+        assert_eq!(result.states, 2);
+    }
+
+    #[test]
+    fn query_uses_state_equation() {
+        let result = query_reachable(
+            vec![1, 0],
+            ReachabilityQuery::Marking {
+                marking: vec![1, 1],
+            },
+            vec![vec![0, 1], vec![1, 0]],
+            vec![vec![1, 0], vec![0, 1]],
+        )
+        .unwrap();
+
+        assert_eq!(result.reachable, Some(false));
+        assert_eq!(result.states, 0);
+    }
+
+    #[test]
+    fn query_rejects_unknown_places() {
+        let result = query_reachable(vec![1], at_least(1, 1), vec![vec![1]], vec![vec![0]]);
+        assert!(result.is_err());
+    }
+
+    fn check_ctl_on_mutex(formula: &str) -> Result<CtlResponse, String> {
+        // Two processes (idle, critical) share a mutex: places idle1, crit1, idle2, crit2, mutex
+        let net = Net::new(
+            &vec![
+                vec![1, 0, 0, 0, 1],
+                vec![0, 1, 0, 0, 0],
+                vec![0, 0, 1, 0, 1],
+                vec![0, 0, 0, 1, 0],
+            ],
+            &vec![
+                vec![0, 1, 0, 0, 0],
+                vec![1, 0, 0, 0, 1],
+                vec![0, 0, 0, 1, 0],
+                vec![0, 0, 1, 0, 1],
+            ],
+        )?;
+        crate::model_checking::check_ctl(vec![1, 0, 1, 0, 1], formula.to_string(), net)
+    }
+
+    #[test]
+    fn ctl_mutual_exclusion() {
+        let safe = check_ctl_on_mutex("AG !(p1 >= 1 & p3 >= 1)").unwrap();
+        assert!(safe.holds);
+        assert_eq!(safe.satisfying_states.len(), 3);
+
+        let critical = check_ctl_on_mutex("EF p1 = 1").unwrap();
+        assert!(critical.holds);
+
+        let live = check_ctl_on_mutex("AG EF en(t0) & AG (p1 >= 1 -> AF p0 >= 1)").unwrap();
+        assert!(live.holds);
+
+        let next = check_ctl_on_mutex("AX (p1 >= 1 | p3 >= 1)").unwrap();
+        assert!(next.holds);
+        assert_eq!(next.satisfying_states, vec![vec![1, 0, 1, 0, 1]]);
+    }
+
+    #[test]
+    fn ctl_treats_dead_markings_as_loops() {
+        // Simple net (1)──>███
+        let check = |formula: &str| {
+            let net = Net::new(&vec![vec![1]], &vec![vec![0]]).unwrap();
+            crate::model_checking::check_ctl(vec![1], formula.to_string(), net).unwrap()
+        };
+
+        assert!(check("AF dead").holds);
+        assert!(check("A[p0 >= 1 U dead]").holds);
+        assert!(!check("EG p0 >= 1").holds);
+        assert_eq!(check("EG p0 == 0").satisfying_states, vec![vec![0]]);
+        assert!(check("EX EX dead").holds);
+    }
+
+    #[test]
+    fn temporal_atoms_are_expressions() {
+        let sum = check_ctl_on_mutex("AG (p1 + p3 + p4 == 1) & AG ((p0 + p1) * 2 == 2)").unwrap();
+        assert!(sum.holds);
+
+        let nested = check_ctl_on_mutex("AG ((p1 >= 1 | p3 >= 1) -> !enabled(t0))").unwrap();
+        assert!(nested.holds);
+
+        let ltl = check_ltl_on_mutex("G (p1 - p3 != 0 -> X p4 == 0)").unwrap();
+        assert!(!ltl.holds);
+
+        let named = Net::new(&vec![vec![1, 0]], &vec![vec![0, 1]])
+            .unwrap()
+            .with_place_names(Some(vec!["ready".to_string(), "done".to_string()]))
+            .unwrap();
+        let result =
+            crate::model_checking::check_ltl(vec![1, 0], "F (done == 1 & dead)".to_string(), named)
+                .unwrap();
+        assert!(result.holds);
+    }
+
+    #[test]
+    fn ctl_reports_parse_errors() {
+        let unclosed = check_ctl_on_mutex("AG (p0 >= 1").unwrap_err();
+        assert_eq!(
+            unclosed,
+            "❌Expected ')' at position 12, but found the end!"
+        );
+
+        let unknown = check_ctl_on_mutex("EF p7 > 0").unwrap_err();
+        assert_eq!(unknown, "❌The net has no p7 at position 4!");
+
+        let until = check_ctl_on_mutex("E[p0 >= 1 p1 >= 1]").unwrap_err();
+        assert!(until.contains("Expected 'U' at position 11"));
+    }
+
+    #[test]
+    fn ctl_needs_bounded_nets() {
+        // ███──>(0)
+        let net = Net::new(&vec![vec![0]], &vec![vec![1]]).unwrap();
+        let error =
+            crate::model_checking::check_ctl(vec![0], "AG p0 >= 0".to_string(), net).unwrap_err();
+
+        assert_eq!(
+            error,
+            "❌The net is unbounded, so its reachability graph is infinite!"
+        );
+    }
+
+    fn check_ltl_on_mutex(formula: &str) -> Result<LtlResponse, String> {
+        // Two processes (idle, critical) share a mutex: places idle1, crit1, idle2, crit2, mutex
+        let net = Net::new(
+            &vec![
+                vec![1, 0, 0, 0, 1],
+                vec![0, 1, 0, 0, 0],
+                vec![0, 0, 1, 0, 1],
+                vec![0, 0, 0, 1, 0],
+            ],
+            &vec![
+                vec![0, 1, 0, 0, 0],
+                vec![1, 0, 0, 0, 1],
+                vec![0, 0, 0, 1, 0],
+                vec![0, 0, 1, 0, 1],
+            ],
+        )?;
+        crate::model_checking::check_ltl(vec![1, 0, 1, 0, 1], formula.to_string(), net)
+    }
+
+    #[test]
+    fn ltl_mutual_exclusion() {
+        let safe = check_ltl_on_mutex("G !(p1 >= 1 & p3 >= 1)").unwrap();
+        assert!(safe.holds);
+        assert!(safe.counterexample.is_none());
+
+        let release = check_ltl_on_mutex("G (fired(t0) -> X fired(t1))").unwrap();
+        assert!(release.holds);
+
+        let until = check_ltl_on_mutex("G (p1 >= 1 -> p1 >= 1 U fired(t1))").unwrap();
+        assert!(until.holds);
+    }
+
+    #[test]
+    fn ltl_returns_lasso_counterexamples() {
+        // Without fairness the second process can enter its critical section forever
+        let starving = check_ltl_on_mutex("G F fired(t0)").unwrap();
+        assert!(!starving.holds);
+        let lasso = starving.counterexample.unwrap();
+        assert!(lasso.stem.is_empty());
+        let cycle: Vec<Option<i16>> = lasso.cycle.iter().map(|step| step.transition).collect();
+        assert_eq!(cycle, vec![Some(2), Some(3)]);
+        assert_eq!(lasso.cycle[0].marking, vec![1, 0, 1, 0, 1]);
+        assert_eq!(lasso.cycle[1].marking, vec![1, 0, 0, 1, 0]);
+
+        // Simple net (1)──>███
+        let check = |formula: &str| {
+            let net = Net::new(&vec![vec![1]], &vec![vec![0]]).unwrap();
+            crate::model_checking::check_ltl(vec![1], formula.to_string(), net).unwrap()
+        };
+        assert!(check("F dead").holds);
+        assert!(check("fired(t0) & X G p0 == 0").holds);
+        let dead_end = check("G F fired(t0)").counterexample.unwrap();
+        assert_eq!(dead_end.stem.len(), 1);
+        assert_eq!(dead_end.stem[0].marking, vec![1]);
+        assert_eq!(dead_end.stem[0].transition, Some(0));
+        assert_eq!(dead_end.cycle.len(), 1);
+        assert_eq!(dead_end.cycle[0].marking, vec![0]);
+        assert_eq!(dead_end.cycle[0].transition, None);
+    }
+
+    #[test]
+    fn ltl_holds_on_forced_cycles() {
+        // Cycle net (1)──>███──>( )──>███──>(back)
+        let net = Net::new(&vec![vec![1, 0], vec![0, 1]], &vec![vec![0, 1], vec![1, 0]]).unwrap();
+        let result = crate::model_checking::check_ltl(
+            vec![1, 0],
+            "G F fired(t1) & G (fired(t0) -> X fired(t1))".to_string(),
+            net,
+        )
+        .unwrap();
+        assert!(result.holds);
+    }
+
+    #[test]
+    fn ltl_reports_parse_errors() {
+        let unclosed = check_ltl_on_mutex("G (p0 >= 1").unwrap_err();
+        assert_eq!(
+            unclosed,
+            "❌Expected ')' at position 11, but found the end!"
+        );
+
+        let unknown = check_ltl_on_mutex("F fired(t9)").unwrap_err();
+        assert_eq!(unknown, "❌The net has no t9 at position 9!");
+
+        let dangling = check_ltl_on_mutex("p0 >= 1 U").unwrap_err();
+        assert!(dangling.contains("at position 10, but found the end"));
+    }
+
+    #[test]
+    fn ltl_needs_bounded_nets() {
+        // ███──>(0)
+        let net = Net::new(&vec![vec![0]], &vec![vec![1]]).unwrap();
+        let error =
+            crate::model_checking::check_ltl(vec![0], "G p0 >= 0".to_string(), net).unwrap_err();
+
+        assert_eq!(
+            error,
+            "❌The net is unbounded, so its reachability graph is infinite!"
+        );
+    }
+
+    struct ExpectedRGResponse {
+        pub states: usize,
+        pub edges: usize,
+        pub reversible: bool,
+        pub liveness: bool,
+        pub bounded: i16,
+        pub bounded_vec: Vec<i16>,
+        pub has_deadlock: bool,
+    }
+
     fn assert_result(result: Result<RGResponse, String>, expected: ExpectedRGResponse) {
         match result {
             Ok(rg) => {
@@ -169,9 +985,9 @@ mod tests {
                 assert_eq!(rg.edges, expected.edges);
                 assert_eq!(rg.bounded, expected.bounded);
                 assert_eq!(rg.bounded_vec, expected.bounded_vec);
-                assert_eq!(rg.reversible, expected.reversible);
-                assert_eq!(rg.liveness, expected.liveness);
-                assert_eq!(rg.has_deadlock, expected.has_deadlock)
+                assert_eq!(rg.reversible, Some(expected.reversible));
+                assert_eq!(rg.liveness, Some(expected.liveness));
+                assert_eq!(rg.has_deadlock, Some(expected.has_deadlock))
             }
             Err(msg) => {
                 panic!("Failed: {}", msg)
@@ -179,13 +995,3 @@ mod tests {
         }
     }
 }
-
-struct ExpectedRGResponse {
-    pub states: usize,
-    pub edges: usize,
-    pub reversible: bool,
-    pub liveness: bool,
-    pub bounded: i16,
-    pub bounded_vec: Vec<i16>,
-    pub has_deadlock: bool,
-}
@@ -1,31 +1,24 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::time::Instant;
 
 use petgraph::algo::tarjan_scc;
 use petgraph::Direction;
 use petgraph::graph::{DiGraph, NodeIndex};
-use petgraph::visit::IntoNodeIdentifiers;
 
-use crate::common::{ReachabilityGraph, RGProperties, RGResult};
+use crate::common::{
+    InputState, RGProperties, RGResult, ReachabilityGraph, ReversibilityWitness,
+    TransitionLiveness, OMEGA,
+};
+use crate::model_checking::coverability::shortest_path_to;
 
 pub(super) fn check_properties(result: &RGResult, transitions: usize) -> RGProperties {
     let bounded_vector = get_bounded_vector(&result.rg);
     let k_bounded = bounded_vector.clone().into_iter().max().unwrap();
-    if result.has_deadlock {
-        println!("Deadlock occurred during RG generation. Skip checking properties");
-        return RGProperties {
-            liveness: false,
-            reversible: false,
-            bounded_vec: bounded_vector,
-            k_bounded: k_bounded,
-            has_deadlock: result.has_deadlock,
-        };
-    }
 
     let start_time_properties = Instant::now();
     let rg = &result.rg;
     let sccs = tarjan_scc(&rg);
-    let scc_graph = create_scc_graph(&sccs, &rg, transitions);
+    let scc_graph = create_scc_graph(&sccs, &rg);
     let end_time_properties = Instant::now();
     let elapsed_time_properties = end_time_properties - start_time_properties;
     println!(
@@ -35,33 +28,70 @@ pub(super) fn check_properties(result: &RGResult, transitions: usize) -> RGPrope
         elapsed_time_properties.as_millis()
     );
 
+    let transition_liveness = check_transition_liveness(&sccs, &scc_graph, rg, transitions);
+    let home_states = find_home_states(&sccs, &scc_graph, rg);
+    let reversibility_witness = find_reversibility_witness(&sccs, rg);
+    if result.has_deadlock() {
+        println!("Deadlock occurred during RG generation. Skip checking global properties");
+        return RGProperties {
+            liveness: false,
+            reversible: false,
+            bounded_vec: bounded_vector,
+            k_bounded: k_bounded,
+            has_deadlock: result.has_deadlock(),
+            transition_liveness,
+            home_states,
+            reversibility_witness,
+        };
+    }
+
     let reversible = sccs.len() == 1 && rg.edge_count() > 0;
-    let liveness = check_liveness(&scc_graph);
+    let liveness = transition_liveness.iter().all(|t| t.level == 4);
 
     return RGProperties {
         liveness,
         reversible,
         bounded_vec: bounded_vector,
         k_bounded: k_bounded,
-        has_deadlock: result.has_deadlock,
+        has_deadlock: result.has_deadlock(),
+        transition_liveness,
+        home_states,
+        reversibility_witness,
     };
 }
 
-fn check_liveness(scc_graph: &DiGraph<bool, ()>) -> bool {
-    let mut liveness = true;
-    scc_graph.node_identifiers().for_each(|inx| {
-        let outgoing_edges = scc_graph.edges_directed(inx, Direction::Outgoing);
-        if outgoing_edges.count() == 0 {
-            // this is a terminal SCC, check if it is labeled with true
-            if !scc_graph.node_weight(inx).unwrap() {
-                liveness = false;
-            }
-        };
-    });
-    return liveness;
+/// Classifies every transition by its liveness level. A transition that fires on an edge of the graph is L1-live.
+/// It is L3-live if it fires inside a strongly connected component, because it can then fire infinitely often. In a
+/// finite graph, this is the same as L2. It is live (L4) if it fires inside every terminal component, which can not
+/// be left again.
+fn check_transition_liveness(
+    sccs: &[Vec<NodeIndex>],
+    scc_graph: &DiGraph<HashSet<i16>, ()>,
+    rg: &ReachabilityGraph,
+    transitions: usize,
+) -> Vec<TransitionLiveness> {
+    let fired: HashSet<i16> = rg.edge_weights().copied().collect();
+    let terminal_sccs = terminal_sccs(scc_graph);
+
+    return (0..transitions as i16)
+        .map(|t| {
+            let blocking_scc = terminal_sccs
+                .iter()
+                .find(|&&inx| !scc_graph[inx].contains(&t));
+            let level = match blocking_scc {
+                None => 4,
+                Some(_) if scc_graph.node_weights().any(|labels| labels.contains(&t)) => 3,
+                Some(_) if fired.contains(&t) => 1,
+                Some(_) => 0,
+            };
+            let never_fires_from = blocking_scc.map(|inx| rg[sccs[inx.index()][0]].to_vec());
+            TransitionLiveness::new(level, never_fires_from)
+        })
+        .collect();
 }
 
-fn get_bounded_vector(rg: &ReachabilityGraph) -> Vec<i16> {
+/// Returns the maximum number of tokens of each place, or -1 if a coverability graph marks it as unbounded.
+pub(super) fn get_bounded_vector(rg: &ReachabilityGraph) -> Vec<i16> {
     let length = rg.node_weight(NodeIndex::new(0)).unwrap().len();
     let mut max_values = vec![-1; length];
 
@@ -73,16 +103,63 @@ fn get_bounded_vector(rg: &ReachabilityGraph) -> Vec<i16> {
             .collect();
     }
 
-    return max_values;
+    return max_values
+        .into_iter()
+        .map(|max| if max == OMEGA { -1 } else { max })
+        .collect();
+}
+
+/// Returns the markings that are reachable from every reachable marking. In a finite graph, these are the markings
+/// of the terminal strongly connected component, if there is only one.
+fn find_home_states(
+    sccs: &[Vec<NodeIndex>],
+    scc_graph: &DiGraph<HashSet<i16>, ()>,
+    rg: &ReachabilityGraph,
+) -> Vec<InputState> {
+    let mut home_states: Vec<InputState> = match terminal_sccs(scc_graph)[..] {
+        [scc] => sccs[scc.index()]
+            .iter()
+            .map(|&inx| rg[inx].to_vec())
+            .collect(),
+        _ => Vec::new(),
+    };
+    home_states.sort();
+    return home_states;
+}
+
+/// Returns the closest marking from which the initial marking can not be reached again, which is the closest marking
+/// outside of its strongly connected component.
+fn find_reversibility_witness(
+    sccs: &[Vec<NodeIndex>],
+    rg: &ReachabilityGraph,
+) -> Option<ReversibilityWitness> {
+    let start_node = NodeIndex::new(0);
+    let initial_scc: HashSet<NodeIndex> = sccs
+        .iter()
+        .find(|scc| scc.contains(&start_node))?
+        .iter()
+        .copied()
+        .collect();
+    let (path, firing_sequence) =
+        shortest_path_to(rg, start_node, |inx| !initial_scc.contains(&inx))?;
+    let marking = rg[*path.last().unwrap()].to_vec();
+    return Some(ReversibilityWitness::new(marking, firing_sequence));
+}
+
+fn terminal_sccs(scc_graph: &DiGraph<HashSet<i16>, ()>) -> Vec<NodeIndex> {
+    return scc_graph
+        .node_indices()
+        .filter(|&inx| scc_graph.edges_directed(inx, Direction::Outgoing).count() == 0)
+        .collect();
 }
 
 // TODO: compare with petgraph::algo::condensation implementation
+/// Condenses every strongly connected component into one node, labeled with the transitions that fire inside it.
 fn create_scc_graph(
     sccs: &Vec<Vec<NodeIndex>>,
     rg: &ReachabilityGraph,
-    transitions: usize,
-) -> DiGraph<bool, ()> {
-    let mut graph = DiGraph::<bool, ()>::new();
+) -> DiGraph<HashSet<i16>, ()> {
+    let mut graph = DiGraph::<HashSet<i16>, ()>::new();
     let mut node_to_scc: Vec<usize> = vec![0; rg.node_count()];
 
     for (scc_index, scc) in sccs.iter().enumerate() {
@@ -92,12 +169,11 @@ fn create_scc_graph(
     }
 
     for _ in 0..sccs.len() {
-        graph.add_node(false);
+        graph.add_node(HashSet::new());
     }
 
     // HashSet to store unique edges
     let mut unique_edges: HashSet<(usize, usize)> = HashSet::new();
-    let mut scc_transitions: HashMap<usize, HashSet<i16>> = HashMap::new();
 
     for edge in rg.edge_indices() {
         let (source, target) = rg.edge_endpoints(edge).unwrap();
@@ -106,20 +182,7 @@ fn create_scc_graph(
         if source_scc != target_scc && unique_edges.insert((source_scc, target_scc)) {
             graph.add_edge(NodeIndex::new(source_scc), NodeIndex::new(target_scc), ());
         } else if source_scc == target_scc {
-            let entry = scc_transitions
-                .entry(source_scc)
-                .or_insert_with(HashSet::new);
-            entry.insert(*rg.edge_weight(edge).unwrap());
-        }
-    }
-
-    // Label nodes based on the presence of internal edges with each label
-    for (_, node) in graph.node_indices().enumerate() {
-        match scc_transitions.get(&(node.index())) {
-            Some(labels) => {
-                graph[node] = labels.len() == transitions;
-            }
-            None => {}
+            graph[NodeIndex::new(source_scc)].insert(*rg.edge_weight(edge).unwrap());
         }
     }
 
@@ -0,0 +1,116 @@
+use std::collections::{HashSet, VecDeque};
+
+use ndarray::arr1;
+
+use crate::common::*;
+use crate::expression::{self, Expression};
+
+/// The search gives up after exploring this many markings, so that it terminates for unbounded nets.
+const MAX_QUERY_STATES: usize = 1_000_000;
+
+pub(super) struct QueryResult {
+    /// The shortest firing sequence to a marking that satisfies the query, and the markings along it.
+    pub(super) witness: Option<(InputState, Vec<InputState>)>,
+    pub(super) states: usize,
+    /// Whether every reachable marking was explored.
+    pub(super) exhausted: bool,
+}
+
+/// Converts the query into a condition on markings and checks that it only refers to places of the net.
+pub(super) fn compile_query(
+    query: &ReachabilityQuery,
+    marking: &InputState,
+    net: &Net,
+) -> Result<Expression, String> {
+    let tokens = |place: usize, comparison: Comparison, tokens: i16| {
+        Expression::Compare(
+            comparison,
+            Box::new(Expression::Tokens(place)),
+            Box::new(Expression::Number(tokens as i64)),
+        )
+    };
+    return match query {
+        ReachabilityQuery::Marking { marking: target } => {
+            if target.len() != marking.len() {
+                return Err("❌The target does not match the places of the net!".to_string());
+            }
+            net.check_marking(target)?;
+            Ok(expression::all(
+                target
+                    .iter()
+                    .enumerate()
+                    .map(|(p, &target)| tokens(p, Comparison::Equal, target)),
+            ))
+        }
+        ReachabilityQuery::Tokens { conditions } => {
+            if let Some(condition) = conditions.iter().find(|c| c.place >= marking.len()) {
+                return Err(format!(
+                    "❌The query refers to place {}, which does not exist!",
+                    condition.place
+                ));
+            }
+            Ok(expression::all(conditions.iter().map(|condition| {
+                tokens(condition.place, condition.comparison, condition.tokens)
+            })))
+        }
+        ReachabilityQuery::Expression { expression } => {
+            expression::parse_condition(expression, net)
+        }
+    };
+}
+
+/// Explores the markings breadth-first and stops at the first one that satisfies the condition, so that its firing
+/// sequence is as short as possible.
+pub(super) fn search(marking: &InputState, condition: &Expression, net: &Net) -> QueryResult {
+    // every node stores its state and the node and transition it was reached from
+    let mut nodes: Vec<(State, Option<(usize, i16)>)> = vec![(arr1(marking), None)];
+    let mut visited: HashSet<State> = HashSet::from([arr1(marking)]);
+    let mut queue: VecDeque<usize> = VecDeque::from([0]);
+
+    while let Some(node) = queue.pop_front() {
+        let state = nodes[node].0.clone();
+        if condition.holds(&state, net) {
+            return QueryResult {
+                witness: Some(witness(&nodes, node)),
+                states: nodes.len(),
+                exhausted: false,
+            };
+        }
+        // stop before the token counts overflow, like the simulator does
+        if nodes.len() >= MAX_QUERY_STATES || state.iter().any(|&tokens| tokens > 30000) {
+            return QueryResult {
+                witness: None,
+                states: nodes.len(),
+                exhausted: false,
+            };
+        }
+        for t in net.prioritized(find_active_transitions(&state, net)) {
+            let next_state = fire_transition(&state, net, t as usize);
+            if visited.insert(next_state.clone()) {
+                queue.push_back(nodes.len());
+                nodes.push((next_state, Some((node, t))));
+            }
+        }
+    }
+    return QueryResult {
+        witness: None,
+        states: nodes.len(),
+        exhausted: true,
+    };
+}
+
+fn witness(
+    nodes: &[(State, Option<(usize, i16)>)],
+    mut node: usize,
+) -> (InputState, Vec<InputState>) {
+    let mut firing_sequence = Vec::new();
+    let mut markings = vec![nodes[node].0.to_vec()];
+    while let Some((parent, t)) = nodes[node].1 {
+        firing_sequence.push(t);
+        markings.push(nodes[parent].0.to_vec());
+        node = parent;
+    }
+    firing_sequence.reverse();
+    markings.reverse();
+    return (firing_sequence, markings);
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
 use ndarray::arr1;
@@ -6,25 +6,52 @@ use petgraph::dot::{Config, Dot};
 use petgraph::graph::{DiGraph, EdgeReference, NodeIndex};
 
 use crate::common::*;
-use crate::model_checking::coverability::is_covering;
+use crate::model_checking::coverability::{
+    covering_witness, find_unbounded_witness, shortest_path_tree, tree_path,
+};
+
+/// The reachability graph is only created up to this many states.
+const MAX_RG_STATES: usize = 1_000_000;
+
+/// Why the reachability graph could not be created
+pub(super) enum RGError {
+    /// A marking strictly covers an earlier marking of the firing sequence that reached it, so the net is unbounded.
+    Unbounded(UnboundedWitness),
+    /// The graph has too many states or a place comes close to overflowing, but no covering proves that the net is
+    /// unbounded.
+    TooLarge,
+}
 
-pub(super) fn create_rg(
-    marking: InputState,
-    transition_inputs: InputMatrix,
-    transition_outputs: InputMatrix,
-) -> Result<RGResult, String> {
-    let start_time_rg = Instant::now();
+impl RGError {
+    pub(super) fn message(&self) -> String {
+        return match self {
+            RGError::Unbounded(_) => {
+                "❌The net is unbounded, so its reachability graph is infinite!".to_string()
+            }
+            RGError::TooLarge => format!(
+                "❌The reachability graph has more than {} states or more than 30000 tokens in a place!",
+                MAX_RG_STATES
+            ),
+        };
+    }
+}
 
-    let t_in: PTMatrix = input_matrix_to_matrix(&transition_inputs);
-    let t_out: PTMatrix = input_matrix_to_matrix(&transition_outputs);
-    let t_effect: PTMatrix = &t_out - &t_in;
+/// Creates the reachability graph of the net. Fails with a witness if a covering proves that the net is unbounded.
+pub(super) fn create_rg(marking: InputState, net: &Net) -> Result<RGResult, RGError> {
+    let start_time_rg = Instant::now();
 
+    let non_monotonic_places = net.non_monotonic_places();
     let state_vec = arr1(&marking);
     let mut queue: Vec<NodeIndex> = Vec::new();
     let mut graph = DiGraph::<State, i16>::new();
     let mut all_states_rev: HashMap<State, NodeIndex> = HashMap::new();
+    // the node and transition that first reached each node, whose firing sequences are searched for coverings
+    let mut reached_by: HashMap<NodeIndex, (NodeIndex, i16)> = HashMap::new();
+    // a covering only proves unboundedness if monotonic places grow, so only markings that exceed the maximum of a
+    // monotonic place are checked, which keeps the search cheap
+    let mut maxima = state_vec.clone();
 
-    let mut has_deadlock = false;
+    let mut dead_nodes: Vec<NodeIndex> = Vec::new();
 
     // create & insert start node
     let start_node = graph.add_node(state_vec.clone());
@@ -34,24 +61,47 @@ pub(super) fn create_rg(
     while !queue.is_empty() {
         let cur_state_idx = queue.pop().unwrap();
         let cur_state = graph.node_weight(cur_state_idx).cloned().unwrap();
-        let active: Vec<i16> = find_active_transitions(&cur_state, &t_in);
+        let active: Vec<i16> = net.prioritized(find_active_transitions(&cur_state, net));
 
         if active.is_empty() {
-            has_deadlock = true;
+            dead_nodes.push(cur_state_idx);
         }
 
         for inx in active {
-            let new_state: State = fire_transition(&cur_state, &t_effect, inx as usize);
+            let new_state: State = fire_transition(&cur_state, net, inx as usize);
             match all_states_rev.get(&new_state) {
                 None => {
                     let new_state_idx = graph.add_node(new_state.clone());
                     graph.add_edge(cur_state_idx, new_state_idx, inx);
 
-                    all_states_rev.insert(new_state, new_state_idx);
+                    reached_by.insert(new_state_idx, (cur_state_idx, inx));
                     queue.push(new_state_idx);
-                    if is_covering(&new_state_idx, &graph, true) {
-                        return Err("Net is unbounded".to_string());
+                    if graph.node_count() > MAX_RG_STATES
+                        || new_state.iter().any(|&tokens| tokens > 30000)
+                    {
+                        return Err(RGError::TooLarge);
+                    }
+
+                    let new_maximum = (0..new_state.len())
+                        .any(|p| !non_monotonic_places[p] && new_state[p] > maxima[p]);
+                    if new_maximum {
+                        maxima.zip_mut_with(&new_state, |max, &tokens| *max = tokens.max(*max));
+                        let (path, firing_sequence) =
+                            tree_path(&reached_by, start_node, new_state_idx).unwrap();
+                        if let Some(witness) =
+                            covering_witness(&graph, &path, &firing_sequence, &non_monotonic_places)
+                        {
+                            // the shortest firing sequence is easier to follow, if it also contains a covering
+                            let shortest = find_unbounded_witness(
+                                &graph,
+                                start_node,
+                                new_state_idx,
+                                &non_monotonic_places,
+                            );
+                            return Err(RGError::Unbounded(shortest.unwrap_or(witness)));
+                        }
                     }
+                    all_states_rev.insert(new_state, new_state_idx);
                 }
                 Some(existing_node_index) => {
                     graph.add_edge(cur_state_idx, existing_node_index.clone(), inx);
@@ -78,11 +128,36 @@ pub(super) fn create_rg(
 
     return Ok(RGResult {
         rg: graph,
-        has_deadlock: has_deadlock,
+        dead_nodes,
     });
 }
 
-pub(super) fn create_dot_graph(reachability_graph: &ReachabilityGraph) -> String {
+/// Returns every dead marking of the graph with the shortest firing sequence from the initial marking.
+pub(super) fn find_deadlocks(result: &RGResult) -> Vec<Deadlock> {
+    let start_node = NodeIndex::new(0);
+    let reached_by = shortest_path_tree(&result.rg, start_node);
+    let mut deadlocks: Vec<Deadlock> = result
+        .dead_nodes
+        .iter()
+        .filter_map(|&node| {
+            let (_, firing_sequence) = tree_path(&reached_by, start_node, node)?;
+            let marking = result.rg[node]
+                .iter()
+                .map(|&tokens| if tokens == OMEGA { -1 } else { tokens })
+                .collect();
+            Some(Deadlock::new(marking, firing_sequence))
+        })
+        .collect();
+    deadlocks.sort_by_key(|deadlock| deadlock.firing_sequence.len());
+    return deadlocks;
+}
+
+/// Creates the DOT representation of the graph, where the initial marking and the dead markings are highlighted.
+pub(super) fn create_dot_graph(
+    reachability_graph: &ReachabilityGraph,
+    dead_nodes: &[NodeIndex],
+) -> String {
+    let dead_nodes: HashSet<&NodeIndex> = dead_nodes.iter().collect();
     let edge_extractor = |_: &ReachabilityGraph, edge: EdgeReference<i16>| -> String {
         return format!("label = \"t{}\"", edge.weight());
     };
@@ -90,11 +165,17 @@ pub(super) fn create_dot_graph(reachability_graph: &ReachabilityGraph) -> String
         if state.0.index() == 0 {
             // this is the starting node of the graph, which should have different colorization
             return format!(
-                "label = \"{:?}\" style = \"filled,bold\" color=\"#673AB7\" fontcolor=\"#FFFFFF\"",
-                state.1.to_vec()
+                "label = \"{}\" style = \"filled,bold\" color=\"#673AB7\" fontcolor=\"#FFFFFF\"",
+                format_marking(state.1)
+            );
+        }
+        if dead_nodes.contains(&state.0) {
+            return format!(
+                "label = \"{}\" style = \"filled\" color=\"#F44336\" fontcolor=\"#FFFFFF\"",
+                format_marking(state.1)
             );
         }
-        return format!("label = \"{:?}\"", state.1.to_vec());
+        return format!("label = \"{}\"", format_marking(state.1));
     };
 
     return format!(
@@ -107,3 +188,15 @@ pub(super) fn create_dot_graph(reachability_graph: &ReachabilityGraph) -> String
         )
     );
 }
+
+/// Formats the marking like a list and shows the places of coverability graph nodes that are unbounded as ω.
+fn format_marking(marking: &State) -> String {
+    let tokens: Vec<String> = marking
+        .iter()
+        .map(|&tokens| match tokens {
+            OMEGA => "ω".to_string(),
+            _ => tokens.to_string(),
+        })
+        .collect();
+    return format!("[{}]", tokens.join(", "));
+}
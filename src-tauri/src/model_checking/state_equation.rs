@@ -0,0 +1,122 @@
+use std::collections::HashSet;
+
+use microlp::{ComparisonOp, OptimizationDirection, Problem};
+use ndarray::arr1;
+
+use crate::common::*;
+
+/// The guided search gives up after visiting this many partial firing count vectors.
+const MAX_SEARCH_STATES: usize = 100_000;
+
+/// Finds the smallest non-negative integer vector x with `target = marking + C·x`, where C is the incidence matrix.
+/// Returns `Ok(None)` if the state equation has no solution, which proves that the target is unreachable.
+pub(super) fn solve_state_equation(
+    marking: &InputState,
+    target: &InputState,
+    net: &Net,
+) -> Result<Option<Vec<i64>>, String> {
+    let mut problem = Problem::new(OptimizationDirection::Minimize);
+    let counts: Vec<_> = (0..net.transition_count())
+        .map(|_| problem.add_integer_var(1.0, (0, i32::MAX)))
+        .collect();
+    for p in 0..target.len() {
+        let effects: Vec<_> = counts
+            .iter()
+            .enumerate()
+            .filter(|&(t, _)| net.t_effect[[t, p]] != 0)
+            .map(|(t, &count)| (count, net.t_effect[[t, p]] as f64))
+            .collect();
+        let difference = target[p] - marking[p];
+        if effects.is_empty() && difference != 0 {
+            return Ok(None);
+        }
+        problem.add_constraint(effects, ComparisonOp::Eq, difference as f64);
+    }
+
+    let solution = match problem.solve() {
+        Ok(solution) => solution,
+        Err(microlp::Error::Infeasible) => return Ok(None),
+        Err(error) => return Err(format!("❌Could not solve the state equation: {}", error)),
+    };
+    let parikh_vector: Vec<i64> = counts
+        .iter()
+        .map(|&count| solution.var_value(count).round() as i64)
+        .collect();
+
+    // the solver works with floating point numbers, so the rounded solution is checked exactly
+    let solves = (0..target.len()).all(|p| {
+        let produced: i64 = (0..net.transition_count())
+            .map(|t| net.t_effect[[t, p]] as i64 * parikh_vector[t])
+            .sum();
+        marking[p] as i64 + produced == target[p] as i64
+    });
+    if !solves {
+        return Err("❌The solver returned an invalid solution of the state equation".to_string());
+    }
+    return Ok(Some(parikh_vector));
+}
+
+/// A state of the guided search, with the node and transition it was reached from
+struct SearchNode {
+    state: State,
+    remaining: Vec<i64>,
+    reached_from: Option<(usize, i16)>,
+}
+
+/// Searches for a firing sequence from the marking that fires every transition exactly as often as the Parikh vector
+/// says. Without reset arcs, the remaining firing counts determine the marking, so they identify the visited states.
+pub(super) fn find_firing_sequence(
+    marking: &InputState,
+    parikh_vector: &[i64],
+    net: &Net,
+) -> Option<InputState> {
+    let mut nodes = vec![SearchNode {
+        state: arr1(marking),
+        remaining: parikh_vector.to_vec(),
+        reached_from: None,
+    }];
+    let mut visited: HashSet<Vec<i64>> = HashSet::from([parikh_vector.to_vec()]);
+    let mut stack: Vec<usize> = vec![0];
+
+    while let Some(node) = stack.pop() {
+        let SearchNode {
+            state, remaining, ..
+        } = &nodes[node];
+        if remaining.iter().all(|&count| count == 0) {
+            return Some(firing_sequence(&nodes, node));
+        }
+        let mut successors = Vec::new();
+        for t in net.prioritized(find_active_transitions(state, net)) {
+            if remaining[t as usize] == 0 {
+                continue;
+            }
+            let mut next_remaining = remaining.clone();
+            next_remaining[t as usize] -= 1;
+            if visited.insert(next_remaining.clone()) {
+                successors.push(SearchNode {
+                    state: fire_transition(state, net, t as usize),
+                    remaining: next_remaining,
+                    reached_from: Some((node, t)),
+                });
+            }
+        }
+        for successor in successors {
+            stack.push(nodes.len());
+            nodes.push(successor);
+        }
+        if visited.len() > MAX_SEARCH_STATES {
+            break;
+        }
+    }
+    return None;
+}
+
+fn firing_sequence(nodes: &[SearchNode], mut node: usize) -> InputState {
+    let mut sequence = Vec::new();
+    while let Some((parent, t)) = nodes[node].reached_from {
+        sequence.push(t);
+        node = parent;
+    }
+    sequence.reverse();
+    return sequence;
+}
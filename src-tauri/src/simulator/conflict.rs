@@ -0,0 +1,90 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::common::{ConflictPolicy, InputState, Net};
+use crate::simulator::stochastic::select_weighted;
+
+/// Resolves conflicts between enabled transitions according to the policy of a simulation session.
+pub(super) struct ConflictResolver {
+    policy: ConflictPolicy,
+    last_fired: Option<usize>,
+}
+
+/// Checks that the parameters of the policy match the net.
+pub(super) fn validate_conflict_policy(
+    policy: &ConflictPolicy,
+    transition_count: usize,
+) -> Result<(), String> {
+    if let ConflictPolicy::Weighted { weights } = policy {
+        if weights.len() != transition_count {
+            return Err(format!(
+                "❌Expected weights for {} transitions, but got {}!",
+                transition_count,
+                weights.len()
+            ));
+        }
+        if let Some(t) = weights.iter().position(|w| !w.is_finite() || *w <= 0.0) {
+            return Err(format!("❌Transition {} needs a positive weight!", t));
+        }
+    }
+    Ok(())
+}
+
+impl ConflictResolver {
+    pub(super) fn new(policy: ConflictPolicy) -> Self {
+        ConflictResolver {
+            policy,
+            last_fired: None,
+        }
+    }
+
+    /// Selects the transition to fire among the active transitions with the highest priority.
+    pub(super) fn select(
+        &mut self,
+        active_transitions: &InputState,
+        net: &Net,
+        rng: &mut StdRng,
+    ) -> usize {
+        let fired = if net.priorities.is_some() {
+            let candidates = net.prioritized(active_transitions.clone());
+            self.select_candidate(&candidates, net.transition_count(), rng)
+        } else {
+            self.select_candidate(active_transitions, net.transition_count(), rng)
+        };
+        self.last_fired = Some(fired);
+        fired
+    }
+
+    fn select_candidate(
+        &self,
+        candidates: &InputState,
+        transition_count: usize,
+        rng: &mut StdRng,
+    ) -> usize {
+        return match &self.policy {
+            ConflictPolicy::Uniform => {
+                let rng_index: usize = rng.gen_range(0..candidates.len());
+                candidates[rng_index] as usize
+            }
+            ConflictPolicy::Weighted { weights } => {
+                let candidates: Vec<(usize, f64)> = candidates
+                    .iter()
+                    .map(|&t| (t as usize, weights[t as usize]))
+                    .collect();
+                select_weighted(&candidates, rng)
+            }
+            ConflictPolicy::RoundRobin => {
+                // the distance to the transition after the last fired one, wrapping around at the end
+                let start = self.last_fired.map_or(0, |t| t + 1);
+                let distance =
+                    |t: usize| (t + transition_count - start % transition_count) % transition_count;
+                candidates
+                    .iter()
+                    .map(|&t| t as usize)
+                    .min_by_key(|&t| distance(t))
+                    .unwrap()
+            }
+            ConflictPolicy::FirstEnabled => *candidates.iter().min().unwrap() as usize,
+        };
+    }
+}
@@ -0,0 +1,594 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+
+use derive_new::new;
+use lazy_static::lazy_static;
+use ndarray::arr1;
+use petgraph::matrix_graph::Zero;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::common::*;
+use crate::expression::Expression;
+use crate::simulator::conflict::{validate_conflict_policy, ConflictResolver};
+use crate::simulator::stochastic::{select_stochastic_transition, validate_stochastic_transitions};
+use crate::simulator::timed::{validate_firing_delays, TimedSimulation};
+
+mod conflict;
+mod mod_test;
+mod stochastic;
+mod timed;
+
+struct SimulatorState {
+    session: SessionId,
+    state: State,
+    net: Net,
+    deadlocked: bool,
+    firing_updates: FiringUpdates,
+    seed: u64,
+    rng: StdRng,
+    trace_options: Option<TraceOptions>,
+    stochastic: Option<Vec<StochasticTransition>>,
+    timed: Option<TimedSimulation>,
+    resolver: ConflictResolver,
+    model_time: f64,
+    stop_condition: Option<Expression>,
+}
+
+impl SimulatorState {
+    /// Creates the response for the current marking of the session.
+    fn response(&self, firings: Vec<u64>, steps: usize) -> SimulationResponse {
+        let is_timed = self.stochastic.is_some() || self.timed.is_some();
+        let model_time = is_timed.then_some(self.model_time);
+        SimulationResponse::new(
+            self.state.to_vec(),
+            firings,
+            self.deadlocked,
+            steps,
+            self.seed,
+            self.session,
+        )
+        .with_model_time(model_time)
+    }
+}
+
+/// Optional settings of a simulation session
+#[derive(Debug, Default, new)]
+pub(crate) struct SimulationOptions {
+    /// The seed of the random number generator. A random seed is generated if none is given.
+    pub(crate) seed: Option<u64>,
+    pub(crate) trace: Option<TraceOptions>,
+    /// Simulates the net as a generalized stochastic Petri net with the given timing of each transition.
+    pub(crate) stochastic: Option<Vec<StochasticTransition>>,
+    /// Simulates the net as a timed Petri net with the given firing delay of each transition.
+    pub(crate) timed: Option<Vec<FiringDelay>>,
+    /// Decides which of the enabled transitions fires. Stochastic simulations only support the uniform policy,
+    /// because their conflicts are resolved by rates and weights.
+    #[new(default)]
+    pub(crate) policy: ConflictPolicy,
+    /// Ends a run as soon as a firing reaches a marking that satisfies the condition.
+    #[new(default)]
+    pub(crate) stop_condition: Option<Expression>,
+}
+
+impl SimulationOptions {
+    pub(crate) fn with_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub(crate) fn with_stop_condition(mut self, stop_condition: Option<Expression>) -> Self {
+        self.stop_condition = stop_condition;
+        self
+    }
+}
+
+/// All running simulations, so that every editor window can simulate its own net.
+/// Each session has its own lock, so a long run only blocks requests for the same session.
+#[derive(Default)]
+struct SimulatorSessions {
+    next_session: SessionId,
+    sessions: HashMap<SessionId, Arc<Mutex<SimulatorState>>>,
+}
+
+impl SimulatorSessions {
+    /// Returns the given session id if that session exists, and a new id from the counter otherwise,
+    /// so that a client cannot take an id that is handed out later.
+    fn claim_session(&mut self, session: Option<SessionId>) -> SessionId {
+        if let Some(session) = session.filter(|session| self.sessions.contains_key(session)) {
+            return session;
+        }
+        self.next_session += 1;
+        return self.next_session;
+    }
+
+    fn get(&self, session: SessionId) -> Result<Arc<Mutex<SimulatorState>>, String> {
+        return match self.sessions.get(&session) {
+            Some(state) => Ok(state.clone()),
+            None => Err(format!("❌No simulation with session {}!", session)),
+        };
+    }
+}
+
+/// Seeds are generated below 2^53 so that they survive the round trip through JavaScript numbers.
+const MAX_GENERATED_SEED: u64 = 1 << 53;
+
+/// Bounds a simulation run by wall-clock time, by the number of fired transitions, or by both.
+/// The run stops as soon as the first bound is reached.
+#[derive(Clone, Copy, Debug, new)]
+pub(crate) struct SimulationLimit {
+    pub(crate) time_ms: Option<u128>,
+    pub(crate) steps: Option<usize>,
+}
+
+impl SimulationLimit {
+    fn validate(&self) -> Result<(), String> {
+        if self.time_ms.is_none() && self.steps.is_none() {
+            return Err("❌A simulation needs a time or step limit!".to_string());
+        }
+        Ok(())
+    }
+
+    fn is_reached(&self, start: &Instant, steps: usize) -> bool {
+        let time_reached = self
+            .time_ms
+            .is_some_and(|time_ms| start.elapsed().as_millis() >= time_ms);
+        let steps_reached = self.steps.is_some_and(|max_steps| steps >= max_steps);
+        time_reached || steps_reached
+    }
+}
+
+lazy_static! {
+    static ref SIMULATOR_SESSIONS: Mutex<SimulatorSessions> =
+        Mutex::new(SimulatorSessions::default());
+}
+
+/// Locks the registry only long enough to look up, insert or remove a session. The registry is never
+/// left half-updated, so a poisoned lock can be used as is.
+fn sessions() -> MutexGuard<'static, SimulatorSessions> {
+    return SIMULATOR_SESSIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+}
+
+/// Runs a request on a session. A session whose simulation panicked is in an unknown state and gets dropped.
+fn with_session(
+    session: SessionId,
+    request: impl FnOnce(&mut SimulatorState) -> Result<SimulationResponse, String>,
+) -> Result<SimulationResponse, String> {
+    let state = sessions().get(session)?;
+    let mut state = match state.lock() {
+        Ok(state) => state,
+        Err(_) => {
+            sessions().sessions.remove(&session);
+            return Err(format!("❌Simulation {} failed and was dropped!", session));
+        }
+    };
+    return request(&mut state);
+}
+
+pub(crate) fn start_simulation(
+    session: Option<SessionId>,
+    marking: InputState,
+    net: Net,
+    limit: SimulationLimit,
+    options: SimulationOptions,
+) -> Result<SimulationResponse, String> {
+    limit.validate()?;
+    return start_session(session, marking, net, options, |marking, state| {
+        simulate(marking, limit, state)
+    });
+}
+
+pub(crate) fn start_simulation_step(
+    session: Option<SessionId>,
+    marking: InputState,
+    net: Net,
+    options: SimulationOptions,
+) -> Result<SimulationResponse, String> {
+    return start_session(session, marking, net, options, simulate_step);
+}
+
+/// Creates (or replaces) a session for the net and runs the first simulation request on it.
+fn start_session(
+    session: Option<SessionId>,
+    marking: InputState,
+    net: Net,
+    options: SimulationOptions,
+    run: impl FnOnce(State, &mut SimulatorState) -> Result<SimulationResponse, String>,
+) -> Result<SimulationResponse, String> {
+    if let Some(stochastic) = &options.stochastic {
+        validate_stochastic_transitions(stochastic, net.transition_count())?;
+    }
+    if let Some(delays) = &options.timed {
+        validate_firing_delays(delays, net.transition_count())?;
+    }
+    if options.stochastic.is_some() && options.timed.is_some() {
+        return Err("❌A simulation can either be stochastic or timed, but not both!".to_string());
+    }
+    validate_conflict_policy(&options.policy, net.transition_count())?;
+    if options.stochastic.is_some() && options.policy != ConflictPolicy::Uniform {
+        return Err(
+            "❌Conflicts of a stochastic simulation are resolved by its rates and weights!"
+                .to_string(),
+        );
+    }
+    net.check_marking(&marking)?;
+    let seed = options.seed.unwrap_or_else(generate_seed);
+
+    let session = sessions().claim_session(session);
+    if net.transition_count().is_zero() {
+        return handle_no_transitions(session, marking, seed, options);
+    }
+
+    if net.place_count().is_zero() {
+        // TODO: correctly handle nets with no places
+        return handle_no_transitions(session, marking, seed, options);
+    }
+
+    println!("🆕Starting new simulation {}.", session);
+
+    let new_state = get_fresh_simulator_state(session, marking, net, seed, options);
+    let marking = new_state.state.clone();
+    let new_state = Arc::new(Mutex::new(new_state));
+    // lock the session before others can see it, so that the first request runs first
+    let mut state = new_state.lock().unwrap_or_else(PoisonError::into_inner);
+    sessions().sessions.insert(session, new_state.clone());
+    return run(marking, &mut state);
+}
+
+fn get_fresh_simulator_state(
+    session: SessionId,
+    marking: InputState,
+    net: Net,
+    seed: u64,
+    options: SimulationOptions,
+) -> SimulatorState {
+    let state = arr1(&marking);
+    let firing_updates: FiringUpdates = create_firing_updates(&net);
+    let timed = options
+        .timed
+        .map(|delays| TimedSimulation::new(delays, net.place_count()));
+
+    SimulatorState {
+        session,
+        state,
+        net,
+        deadlocked: false,
+        firing_updates,
+        seed,
+        rng: StdRng::seed_from_u64(seed),
+        trace_options: options.trace,
+        stochastic: options.stochastic,
+        timed,
+        resolver: ConflictResolver::new(options.policy),
+        model_time: 0.0,
+        stop_condition: options.stop_condition,
+    }
+}
+
+fn generate_seed() -> u64 {
+    rand::thread_rng().gen_range(0..MAX_GENERATED_SEED)
+}
+
+pub(crate) fn continue_simulation(
+    session: SessionId,
+    limit: SimulationLimit,
+) -> Result<SimulationResponse, String> {
+    limit.validate()?;
+    return with_session(session, |state| {
+        if state.deadlocked {
+            println!(
+                "☠️Trying to continue but simulation {} is still deadlocked.",
+                session
+            );
+            return Ok(state.response(vec![], 0));
+        }
+        println!("↪️Continuing simulation {}.", session);
+        simulate(state.state.clone(), limit, state)
+    });
+}
+
+pub(crate) fn list_simulations() -> Result<Vec<SimulationSession>, String> {
+    let states: Vec<Arc<Mutex<SimulatorState>>> = sessions().sessions.values().cloned().collect();
+    let mut result: Vec<SimulationSession> = states
+        .iter()
+        // sessions whose simulation panicked are dropped on their next request
+        .filter_map(|state| state.lock().ok())
+        .map(|state| {
+            SimulationSession::new(
+                state.session,
+                state.state.to_vec(),
+                state.deadlocked,
+                state.seed,
+            )
+        })
+        .collect();
+    result.sort_by_key(|info| info.session);
+    return Ok(result);
+}
+
+/// Removes a simulation session. Returns whether the session existed.
+pub(crate) fn drop_simulation(session: SessionId) -> Result<bool, String> {
+    println!("🗑️Dropping simulation {}.", session);
+    return Ok(sessions().sessions.remove(&session).is_some());
+}
+
+fn simulate(
+    marking: State,
+    limit: SimulationLimit,
+    sim: &mut SimulatorState,
+) -> Result<SimulationResponse, String> {
+    if sim.timed.is_some() {
+        return simulate_timed(marking, limit, sim);
+    }
+
+    let mut state_vec = marking.clone();
+    let net = &sim.net;
+    let firing_updates = &sim.firing_updates;
+    let stochastic = sim.stochastic.as_deref();
+    let resolver = &mut sim.resolver;
+    let rng = &mut sim.rng;
+    let stop_condition = sim.stop_condition.as_ref();
+    let mut trace = sim.trace_options.as_ref().map(FiringTrace::new);
+    let mut t_heat: Vec<u64> = vec![0; net.transition_count()];
+
+    let mut active_transitions: InputState = Vec::new();
+    let mut fired: usize = 0;
+    let mut deadlocked = false;
+    let mut stopped = false;
+    let start = Instant::now();
+    let mut step: usize = 1;
+
+    while !limit.is_reached(&start, step - 1) {
+        active_transitions = find_active_transitions_from_firing_set(
+            &state_vec,
+            net,
+            active_transitions,
+            firing_updates,
+            &fired,
+        );
+
+        // check if simulation is deadlocked
+        if active_transitions.is_empty() {
+            println!(
+                "☠️No active transitions at step {} with state {:?}.",
+                step, state_vec
+            );
+            deadlocked = true;
+            break;
+        }
+
+        fired = select_next_transition(
+            &active_transitions,
+            net,
+            stochastic,
+            resolver,
+            rng,
+            &mut sim.model_time,
+        );
+        t_heat[fired] += 1;
+        state_vec = fire_transition(&state_vec, net, fired);
+        if let Some(trace) = trace.as_mut() {
+            trace.record(fired, &state_vec);
+        }
+        step += 1;
+
+        if stop_condition.is_some_and(|condition| condition.holds(&state_vec, net)) {
+            println!(
+                "🛑Stop condition holds at step {} with state {:?}.",
+                step - 1,
+                state_vec
+            );
+            stopped = true;
+            break;
+        }
+
+        // check if the marking is close to overflow
+        if step % 2000 == 0 && state_vec.iter().max().unwrap() > &30000 {
+            println!("⚠️State {:?} is close to integer overflow. Marking simulation as deadlocked to prevent system panic.", state_vec);
+            deadlocked = true;
+            break;
+        }
+    }
+
+    if !deadlocked {
+        let end = Instant::now();
+        let took_ms = (end - start).as_millis();
+
+        println!("🔄Simulating {} steps took {}ms.", step, took_ms);
+    }
+
+    sim.state = state_vec;
+    sim.deadlocked = deadlocked;
+
+    return Ok(sim
+        .response(t_heat, step - 1)
+        .with_trace(trace)
+        .with_stopped(stopped));
+}
+
+fn simulate_step(marking: State, sim: &mut SimulatorState) -> Result<SimulationResponse, String> {
+    if sim.timed.is_some() {
+        return simulate_timed(marking, SimulationLimit::new(None, Some(1)), sim);
+    }
+
+    let mut state_vec = marking.clone();
+    let net = &sim.net;
+    let stochastic = sim.stochastic.as_deref();
+    let resolver = &mut sim.resolver;
+    let rng = &mut sim.rng;
+    let heat = vec![0; net.transition_count()];
+
+    // check if the marking is close to overflow
+    if state_vec.iter().max().unwrap() > &30000 {
+        println!("⚠️State {:?} is close to integer overflow. Marking simulation as deadlocked to prevent system panic.", state_vec);
+
+        sim.deadlocked = true;
+        sim.state = state_vec;
+        return Ok(sim.response(heat, 0));
+    }
+
+    let start = Instant::now();
+    let active_transitions = find_active_transitions(&state_vec, net);
+
+    // check if simulation is deadlocked
+    if active_transitions.is_empty() {
+        println!("☠️No active transitions with state {:?}.", state_vec);
+
+        sim.state = state_vec;
+        sim.deadlocked = true;
+        return Ok(sim.response(heat, 0));
+    }
+
+    let fired = select_next_transition(
+        &active_transitions,
+        net,
+        stochastic,
+        resolver,
+        rng,
+        &mut sim.model_time,
+    );
+    let t_heat = (0..net.transition_count())
+        .map(|i| if i == fired { 1 } else { 0 })
+        .collect();
+    state_vec = fire_transition(&state_vec, net, fired);
+    let trace = sim.trace_options.as_ref().map(|options| {
+        let mut trace = FiringTrace::new(options);
+        trace.record(fired, &state_vec);
+        trace
+    });
+
+    let end = Instant::now();
+    let took_ns = (end - start).as_nanos();
+
+    println!("🔄Simulating 1 step took {}ns.", took_ns);
+
+    let stopped = sim
+        .stop_condition
+        .as_ref()
+        .is_some_and(|condition| condition.holds(&state_vec, net));
+    sim.state = state_vec;
+
+    return Ok(sim
+        .response(t_heat, 1)
+        .with_trace(trace)
+        .with_stopped(stopped));
+}
+
+/// Simulates a timed net with the discrete-event engine of the session instead of firing transitions instantly.
+fn simulate_timed(
+    marking: State,
+    limit: SimulationLimit,
+    sim: &mut SimulatorState,
+) -> Result<SimulationResponse, String> {
+    let mut state_vec = marking;
+    let mut t_heat: Vec<u64> = vec![0; sim.net.transition_count()];
+    let mut trace = sim.trace_options.as_ref().map(FiringTrace::new);
+    let timed = sim.timed.as_mut().unwrap();
+
+    let start = Instant::now();
+    let run = timed.run(
+        &mut state_vec,
+        &sim.net,
+        limit,
+        &mut sim.resolver,
+        &mut sim.rng,
+        &mut sim.model_time,
+        sim.stop_condition.as_ref(),
+        |fired, state| {
+            t_heat[fired] += 1;
+            if let Some(trace) = trace.as_mut() {
+                trace.record(fired, state);
+            }
+        },
+    );
+    let took_ms = start.elapsed().as_millis();
+
+    println!(
+        "🔄Simulating {} steps up to model time {} took {}ms.",
+        run.steps, sim.model_time, took_ms
+    );
+
+    sim.state = state_vec;
+    sim.deadlocked = run.deadlocked;
+
+    return Ok(sim
+        .response(t_heat, run.steps)
+        .with_trace(trace)
+        .with_events(Some(run.events))
+        .with_stopped(run.stopped)
+        .with_zeno(run.zeno));
+}
+
+/// Fires the given sequence of transitions from the marking and stops at the first transition that is not enabled.
+pub(crate) fn replay_trace(
+    marking: InputState,
+    net: Net,
+    transitions: InputState,
+) -> Result<ReplayResponse, String> {
+    net.check_marking(&marking)?;
+    let mut state_vec = arr1(&marking);
+
+    for (fired, &transition) in transitions.iter().enumerate() {
+        if transition < 0 || transition as usize >= net.transition_count() {
+            return Err(format!("❌Transition {} does not exist!", transition));
+        }
+
+        if !net.is_enabled(&state_vec, transition as usize) {
+            println!(
+                "⛔Transition {} of the trace is not enabled after {} firings.",
+                transition, fired
+            );
+            return Ok(ReplayResponse::new(
+                state_vec.to_vec(),
+                fired,
+                Some(transition),
+            ));
+        }
+        state_vec = fire_transition(&state_vec, &net, transition as usize);
+    }
+
+    return Ok(ReplayResponse::new(
+        state_vec.to_vec(),
+        transitions.len(),
+        None,
+    ));
+}
+
+/// Selects the transition to fire next and advances the model time for stochastic simulations.
+fn select_next_transition(
+    active_transitions: &InputState,
+    net: &Net,
+    stochastic: Option<&[StochasticTransition]>,
+    resolver: &mut ConflictResolver,
+    rng: &mut StdRng,
+    model_time: &mut f64,
+) -> usize {
+    return match stochastic {
+        Some(stochastic) => {
+            let candidates = net.prioritized(active_transitions.clone());
+            let (fired, delay) = select_stochastic_transition(&candidates, stochastic, rng);
+            *model_time += delay;
+            fired
+        }
+        None => resolver.select(active_transitions, net, rng),
+    };
+}
+
+fn handle_no_transitions(
+    session: SessionId,
+    marking: InputState,
+    seed: u64,
+    options: SimulationOptions,
+) -> Result<SimulationResponse, String> {
+    let net = Net::new(&Vec::new(), &Vec::new())?;
+    let mut state = get_fresh_simulator_state(session, marking, net, seed, options);
+    state.deadlocked = true;
+    let response = state.response(vec![], 0);
+    sessions()
+        .sessions
+        .insert(session, Arc::new(Mutex::new(state)));
+    return Ok(response);
+}
@@ -0,0 +1,673 @@
+#[cfg(test)]
+mod tests {
+    use crate::common::*;
+    use crate::simulator::*;
+
+    fn net(transition_inputs: InputMatrix, transition_outputs: InputMatrix) -> Net {
+        Net::new(&transition_inputs, &transition_outputs).unwrap()
+    }
+
+    fn two_loops(seed: u64) -> SimulatorState {
+        // A single token that randomly takes one of two loops
+        //  ┌──>███─>(0)─>███───┐
+        // (1)<─────────────────┤
+        //  └──>███─>(0)─>███───┘
+        get_fresh_simulator_state(
+            0,
+            vec![1, 0, 0],
+            net(
+                vec![vec![1, 0, 0], vec![1, 0, 0], vec![0, 1, 0], vec![0, 0, 1]],
+                vec![vec![0, 1, 0], vec![0, 0, 1], vec![1, 0, 0], vec![1, 0, 0]],
+            ),
+            seed,
+            SimulationOptions::default(),
+        )
+    }
+
+    fn run_steps(seed: u64, steps: usize) -> Vec<InputState> {
+        let mut state = two_loops(seed);
+
+        let mut markings = Vec::new();
+        for _ in 0..steps {
+            let response = simulate_step(state.state.clone(), &mut state).unwrap();
+            assert_eq!(response.seed, seed);
+            markings.push(response.marking);
+        }
+        markings
+    }
+
+    #[test]
+    fn same_seed_replays_simulation() {
+        assert_eq!(run_steps(42, 50), run_steps(42, 50));
+    }
+
+    #[test]
+    fn different_seeds_diverge() {
+        assert_ne!(run_steps(1, 50), run_steps(2, 50));
+    }
+
+    #[test]
+    fn step_limit_fires_exact_number_of_transitions() {
+        let mut state = two_loops(7);
+        let limit = SimulationLimit::new(None, Some(1000));
+        let response = simulate(state.state.clone(), limit, &mut state).unwrap();
+
+        assert_eq!(response.steps, 1000);
+        assert_eq!(
+            response.firings.iter().map(|&f| f as usize).sum::<usize>(),
+            1000
+        );
+        assert!(!response.deadlocked);
+    }
+
+    #[test]
+    fn firing_counts_exceed_token_range() {
+        // Loop   ┌──>███─┐
+        //       (1)<─────┘
+        let mut state = get_fresh_simulator_state(
+            0,
+            vec![1],
+            net(vec![vec![1]], vec![vec![1]]),
+            1,
+            SimulationOptions::default(),
+        );
+        let limit = SimulationLimit::new(None, Some(40000));
+        let response = simulate(state.state.clone(), limit, &mut state).unwrap();
+
+        assert_eq!(response.firings, vec![40000]);
+    }
+
+    #[test]
+    fn step_limit_with_time_limit() {
+        let mut state = two_loops(7);
+        let limit = SimulationLimit::new(Some(60_000), Some(10));
+        let response = simulate(state.state.clone(), limit, &mut state).unwrap();
+
+        assert_eq!(response.steps, 10);
+    }
+
+    #[test]
+    fn simulation_without_limit_is_rejected() {
+        let limit = SimulationLimit::new(None, None);
+        let result = start_simulation(
+            None,
+            vec![1],
+            net(vec![vec![1]], vec![vec![1]]),
+            limit,
+            SimulationOptions::default(),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn recorded_trace_can_be_replayed() {
+        let mut state = two_loops(3);
+        state.trace_options = Some(TraceOptions {
+            marking_interval: Some(5),
+        });
+        let limit = SimulationLimit::new(None, Some(20));
+        let response = simulate(state.state.clone(), limit, &mut state).unwrap();
+        let trace = response.trace.unwrap();
+
+        assert_eq!(trace.transitions.len(), 20);
+        assert_eq!(
+            trace.markings.iter().map(|m| m.step).collect::<Vec<_>>(),
+            vec![5, 10, 15, 20]
+        );
+        assert_eq!(trace.markings.last().unwrap().marking, response.marking);
+
+        let replay = replay_trace(
+            vec![1, 0, 0],
+            net(
+                vec![vec![1, 0, 0], vec![1, 0, 0], vec![0, 1, 0], vec![0, 0, 1]],
+                vec![vec![0, 1, 0], vec![0, 0, 1], vec![1, 0, 0], vec![1, 0, 0]],
+            ),
+            trace.transitions,
+        )
+        .unwrap();
+        assert_eq!(replay.marking, response.marking);
+        assert_eq!(replay.fired, 20);
+        assert_eq!(replay.blocked_transition, None);
+    }
+
+    #[test]
+    fn replay_reports_first_disabled_transition() {
+        // (1)──>███──>(0)──>███──>(0)
+        let replay = replay_trace(
+            vec![1, 0, 0],
+            net(
+                vec![vec![1, 0, 0], vec![0, 1, 0]],
+                vec![vec![0, 1, 0], vec![0, 0, 1]],
+            ),
+            vec![0, 1, 1, 0],
+        )
+        .unwrap();
+
+        assert_eq!(replay.marking, vec![0, 0, 1]);
+        assert_eq!(replay.fired, 2);
+        assert_eq!(replay.blocked_transition, Some(1));
+    }
+
+    #[test]
+    fn immediate_transitions_fire_by_priority() {
+        // (1)──>███──>(0)   with an immediate transition of priority 1, one of priority 0 and a timed one
+        //  └──>███──>(0)
+        //  └──>███──>(0)
+        let stochastic = vec![
+            StochasticTransition::Immediate {
+                weight: 1.0,
+                priority: 1,
+            },
+            StochasticTransition::Immediate {
+                weight: 100.0,
+                priority: 0,
+            },
+            StochasticTransition::Timed { rate: 100.0 },
+        ];
+        let response = start_simulation(
+            None,
+            vec![1, 0, 0, 0],
+            net(
+                vec![vec![1, 0, 0, 0], vec![1, 0, 0, 0], vec![1, 0, 0, 0]],
+                vec![vec![0, 1, 0, 0], vec![0, 0, 1, 0], vec![0, 0, 0, 1]],
+            ),
+            SimulationLimit::new(None, Some(10)),
+            SimulationOptions::new(Some(1), None, Some(stochastic), None),
+        )
+        .unwrap();
+
+        assert_eq!(response.marking, vec![0, 1, 0, 0]);
+        assert_eq!(response.model_time, Some(0.0));
+        assert!(response.deadlocked);
+    }
+
+    #[test]
+    fn timed_transitions_advance_model_time() {
+        // Circle with a timed transition of rate 2, so every firing takes 0.5 time units on average
+        //   ┌──>███─>(0)
+        //  (1)<──────┘
+        let mut state = get_fresh_simulator_state(
+            0,
+            vec![1],
+            net(vec![vec![1]], vec![vec![1]]),
+            5,
+            SimulationOptions::new(
+                None,
+                None,
+                Some(vec![StochasticTransition::Timed { rate: 2.0 }]),
+                None,
+            ),
+        );
+        let limit = SimulationLimit::new(None, Some(10000));
+        let response = simulate(state.state.clone(), limit, &mut state).unwrap();
+        let model_time = response.model_time.unwrap();
+
+        assert!(model_time > 4800.0 && model_time < 5200.0, "{}", model_time);
+    }
+
+    #[test]
+    fn stop_condition_ends_the_run() {
+        // A producer fills the buffer
+        // ███──>(0)
+        let net = net(vec![vec![0]], vec![vec![1]]);
+        let condition = crate::expression::parse_condition("p0 % 10 == 0", &net).unwrap();
+        let mut state = get_fresh_simulator_state(
+            0,
+            vec![0],
+            net,
+            1,
+            SimulationOptions::default().with_stop_condition(Some(condition)),
+        );
+        let limit = SimulationLimit::new(None, Some(100));
+
+        let response = simulate(state.state.clone(), limit, &mut state).unwrap();
+        assert!(response.stopped);
+        assert_eq!(response.steps, 10);
+        assert_eq!(response.marking, vec![10]);
+
+        // continuing fires at least once before checking the condition again
+        let response = simulate(state.state.clone(), limit, &mut state).unwrap();
+        assert_eq!(response.marking, vec![20]);
+
+        let response = simulate(
+            state.state.clone(),
+            SimulationLimit::new(None, Some(5)),
+            &mut state,
+        )
+        .unwrap();
+        assert!(!response.stopped);
+        assert_eq!(response.marking, vec![25]);
+    }
+
+    #[test]
+    fn stop_condition_ends_timed_runs() {
+        // The token takes one time unit per round and counts the rounds
+        //   ┌──>███─>(0)
+        //  (1)<──┴─>(0)
+        let net = net(vec![vec![1, 0]], vec![vec![1, 1]]);
+        let condition = crate::expression::parse_condition("p1 >= 3", &net).unwrap();
+        let mut state = get_fresh_simulator_state(
+            0,
+            vec![1, 0],
+            net,
+            1,
+            SimulationOptions::new(
+                None,
+                None,
+                None,
+                Some(vec![FiringDelay::Deterministic { delay: 1.0 }]),
+            )
+            .with_stop_condition(Some(condition)),
+        );
+        let limit = SimulationLimit::new(None, Some(100));
+        let response = simulate(state.state.clone(), limit, &mut state).unwrap();
+
+        assert!(response.stopped);
+        assert_eq!(response.marking, vec![1, 3]);
+        assert_eq!(response.model_time, Some(3.0));
+    }
+
+    #[test]
+    fn timed_stop_condition_ignores_started_firings() {
+        // The token is never missing once the firing completes
+        //   ┌──>███─>(0)
+        //  (1)<──────┘
+        let net = net(vec![vec![1]], vec![vec![1]]);
+        let condition = crate::expression::parse_condition("p0 == 0", &net).unwrap();
+        let mut state = get_fresh_simulator_state(
+            0,
+            vec![1],
+            net,
+            1,
+            SimulationOptions::new(
+                None,
+                None,
+                None,
+                Some(vec![FiringDelay::Deterministic { delay: 1.0 }]),
+            )
+            .with_stop_condition(Some(condition)),
+        );
+        let limit = SimulationLimit::new(None, Some(10));
+        let response = simulate(state.state.clone(), limit, &mut state).unwrap();
+
+        assert!(!response.stopped);
+        assert_eq!(response.steps, 10);
+    }
+
+    #[test]
+    fn untimed_simulation_has_no_model_time() {
+        let mut state = two_loops(1);
+        let limit = SimulationLimit::new(None, Some(10));
+        let response = simulate(state.state.clone(), limit, &mut state).unwrap();
+
+        assert_eq!(response.model_time, None);
+    }
+
+    #[test]
+    fn stochastic_parameters_must_match_net() {
+        let result = start_simulation_step(
+            None,
+            vec![1],
+            net(vec![vec![1], vec![1]], vec![vec![0], vec![0]]),
+            SimulationOptions::new(
+                None,
+                None,
+                Some(vec![StochasticTransition::Timed { rate: 1.0 }]),
+                None,
+            ),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn deterministic_delays_fire_concurrently() {
+        // Pipeline with processing times 3 and 2   (2)──>███──>(0)──>███──>(0)
+        let response = start_simulation(
+            None,
+            vec![2, 0, 0],
+            net(
+                vec![vec![1, 0, 0], vec![0, 1, 0]],
+                vec![vec![0, 1, 0], vec![0, 0, 1]],
+            ),
+            SimulationLimit::new(None, Some(100)),
+            SimulationOptions::new(
+                None,
+                None,
+                None,
+                Some(vec![
+                    FiringDelay::Deterministic { delay: 3.0 },
+                    FiringDelay::Deterministic { delay: 2.0 },
+                ]),
+            ),
+        )
+        .unwrap();
+
+        assert!(response.deadlocked);
+        assert_eq!(response.marking, vec![0, 0, 2]);
+        assert_eq!(response.steps, 4);
+        assert_eq!(response.model_time, Some(5.0));
+        let events = response.events.unwrap();
+        assert_eq!(
+            events.iter().map(|e| e.time).collect::<Vec<_>>(),
+            vec![3.0, 5.0]
+        );
+        assert_eq!(events[0].marking, vec![0, 2, 0]);
+    }
+
+    #[test]
+    fn pending_firings_count_towards_capacities() {
+        // A slow transition fills a place with capacity 1   (5)──>███──>(0)
+        let net = net(vec![vec![1, 0]], vec![vec![0, 1]])
+            .with_capacities(Some(vec![None, Some(1)]))
+            .unwrap();
+        let response = start_simulation(
+            None,
+            vec![5, 0],
+            net,
+            SimulationLimit::new(None, Some(100)),
+            SimulationOptions::new(
+                None,
+                None,
+                None,
+                Some(vec![FiringDelay::Deterministic { delay: 1.0 }]),
+            ),
+        )
+        .unwrap();
+
+        assert!(response.deadlocked);
+        assert_eq!(response.marking, vec![4, 1]);
+        assert_eq!(response.steps, 1);
+        assert_eq!(response.model_time, Some(1.0));
+    }
+
+    #[test]
+    fn zero_delay_loops_are_reported() {
+        // Circle that fires without any delay
+        //   ┌──>███─>(0)
+        //  (1)<──────┘
+        let response = start_simulation(
+            None,
+            vec![1],
+            net(vec![vec![1]], vec![vec![1]]),
+            SimulationLimit::new(None, Some(100000)),
+            SimulationOptions::new(
+                None,
+                None,
+                None,
+                Some(vec![FiringDelay::Deterministic { delay: 0.0 }]),
+            ),
+        )
+        .unwrap();
+
+        assert!(response.zeno);
+        assert!(!response.deadlocked);
+        assert_eq!(response.steps, 10000);
+        assert_eq!(response.model_time, Some(0.0));
+    }
+
+    #[test]
+    fn interval_delays_stay_within_bounds() {
+        // Circle with a firing delay between 1 and 2
+        //   ┌──>███─>(0)
+        //  (1)<──────┘
+        let mut state = get_fresh_simulator_state(
+            0,
+            vec![1],
+            net(vec![vec![1]], vec![vec![1]]),
+            9,
+            SimulationOptions::new(
+                None,
+                None,
+                None,
+                Some(vec![FiringDelay::Interval { min: 1.0, max: 2.0 }]),
+            ),
+        );
+        let limit = SimulationLimit::new(None, Some(101));
+        let response = simulate(state.state.clone(), limit, &mut state).unwrap();
+        let model_time = response.model_time.unwrap();
+
+        // the first firing starts at time 0, so 100 firings have completed
+        assert_eq!(response.events.unwrap().len(), 100);
+        assert!((100.0..=200.0).contains(&model_time), "{}", model_time);
+        assert_eq!(response.marking, vec![0]);
+    }
+
+    #[test]
+    fn simulation_is_either_stochastic_or_timed() {
+        let result = start_simulation_step(
+            None,
+            vec![1],
+            net(vec![vec![1]], vec![vec![0]]),
+            SimulationOptions::new(
+                None,
+                None,
+                Some(vec![StochasticTransition::Timed { rate: 1.0 }]),
+                Some(vec![FiringDelay::Deterministic { delay: 1.0 }]),
+            ),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn inhibitor_arcs_disable_transitions() {
+        // A producer that is inhibited once its output place holds three tokens
+        //  ███──>(0)
+        //   o─────┘
+        let net = net(vec![vec![0]], vec![vec![1]])
+            .with_inhibitors(Some(vec![vec![3]]))
+            .unwrap();
+        let mut state = get_fresh_simulator_state(0, vec![0], net, 1, SimulationOptions::default());
+        let limit = SimulationLimit::new(None, Some(100));
+        let response = simulate(state.state.clone(), limit, &mut state).unwrap();
+
+        assert!(response.deadlocked);
+        assert_eq!(response.marking, vec![3]);
+        assert_eq!(response.steps, 3);
+    }
+
+    #[test]
+    fn reset_and_read_arcs_fire_natively() {
+        // A producer that needs the token in the second place, and a transition that empties the first place
+        //  ███──>(0)<──»███
+        //   └────(1)
+        let net = net(vec![vec![0, 0], vec![0, 0]], vec![vec![1, 0], vec![0, 0]])
+            .with_resets(Some(vec![vec![0, 0], vec![1, 0]]))
+            .and_then(|net| net.with_reads(Some(vec![vec![0, 1], vec![0, 0]])))
+            .unwrap();
+        let replay = replay_trace(vec![0, 1], net, vec![0, 0, 0, 1, 0]).unwrap();
+
+        assert_eq!(replay.marking, vec![1, 1]);
+        assert_eq!(replay.fired, 5);
+
+        let net = self::net(vec![vec![0]], vec![vec![1]])
+            .with_reads(Some(vec![vec![1]]))
+            .unwrap();
+        let replay = replay_trace(vec![0], net, vec![0]).unwrap();
+        assert_eq!(replay.blocked_transition, Some(0));
+    }
+
+    #[test]
+    fn capacities_disable_transitions() {
+        // A token moves between two places, and the second one is full after the first firing
+        //  (2)──>███──>(0)[1]
+        let net = net(vec![vec![1, 0]], vec![vec![0, 1]])
+            .with_capacities(Some(vec![None, Some(1)]))
+            .unwrap();
+        let response = start_simulation(
+            None,
+            vec![2, 0],
+            net,
+            SimulationLimit::new(None, Some(10)),
+            SimulationOptions::default(),
+        )
+        .unwrap();
+
+        assert!(response.deadlocked);
+        assert_eq!(response.marking, vec![1, 1]);
+        assert_eq!(response.steps, 1);
+    }
+
+    fn fired_with_policy(net: Net, policy: ConflictPolicy) -> InputState {
+        let mut state = get_fresh_simulator_state(
+            0,
+            vec![1],
+            net,
+            1,
+            SimulationOptions::new(None, Some(TraceOptions::default()), None, None)
+                .with_policy(policy),
+        );
+        let limit = SimulationLimit::new(None, Some(6));
+        let response = simulate(state.state.clone(), limit, &mut state).unwrap();
+        response.trace.unwrap().transitions
+    }
+
+    fn three_loops() -> Net {
+        // Three transitions that all put the token back into the only place
+        net(
+            vec![vec![1], vec![1], vec![1]],
+            vec![vec![1], vec![1], vec![1]],
+        )
+    }
+
+    #[test]
+    fn round_robin_takes_turns() {
+        let fired = fired_with_policy(three_loops(), ConflictPolicy::RoundRobin);
+        assert_eq!(fired, vec![0, 1, 2, 0, 1, 2]);
+    }
+
+    #[test]
+    fn first_enabled_always_fires_lowest_index() {
+        let fired = fired_with_policy(three_loops(), ConflictPolicy::FirstEnabled);
+        assert_eq!(fired, vec![0; 6]);
+    }
+
+    #[test]
+    fn priorities_restrict_candidates() {
+        let net = three_loops().with_priorities(Some(vec![0, 1, 1])).unwrap();
+        let fired = fired_with_policy(net, ConflictPolicy::FirstEnabled);
+        assert_eq!(fired, vec![1; 6]);
+    }
+
+    #[test]
+    fn weighted_policy_only_fires_weighted_transitions() {
+        let policy = ConflictPolicy::Weighted {
+            weights: vec![1e-12, 1.0, 1e12],
+        };
+        let fired = fired_with_policy(three_loops(), policy);
+        assert_eq!(fired, vec![2; 6]);
+    }
+
+    #[test]
+    fn weighted_policy_must_match_net() {
+        let result = start_simulation_step(
+            None,
+            vec![1],
+            three_loops(),
+            SimulationOptions::default().with_policy(ConflictPolicy::Weighted {
+                weights: vec![1.0, 1.0],
+            }),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn sessions_are_independent() {
+        // Two disjoint nets   (1)──>███──>(0)   and   (0)──>███──>(1)
+        let first = start_simulation_step(
+            None,
+            vec![1, 0],
+            net(vec![vec![1, 0]], vec![vec![0, 1]]),
+            SimulationOptions::default(),
+        )
+        .unwrap();
+        let second = start_simulation_step(
+            None,
+            vec![0, 1],
+            net(vec![vec![0, 1]], vec![vec![1, 0]]),
+            SimulationOptions::default(),
+        )
+        .unwrap();
+        assert_ne!(first.session, second.session);
+
+        let first =
+            continue_simulation(first.session, SimulationLimit::new(Some(10), None)).unwrap();
+        let second =
+            continue_simulation(second.session, SimulationLimit::new(Some(10), None)).unwrap();
+        assert_eq!(first.marking, vec![0, 1]);
+        assert_eq!(second.marking, vec![1, 0]);
+
+        let listed: Vec<SessionId> = list_simulations()
+            .unwrap()
+            .iter()
+            .map(|info| info.session)
+            .collect();
+        assert!(listed.contains(&first.session));
+        assert!(listed.contains(&second.session));
+
+        assert!(drop_simulation(first.session).unwrap());
+        assert!(!drop_simulation(first.session).unwrap());
+        assert!(continue_simulation(first.session, SimulationLimit::new(Some(10), None)).is_err());
+        assert!(continue_simulation(second.session, SimulationLimit::new(Some(10), None)).is_ok());
+    }
+
+    #[test]
+    fn unknown_sessions_get_new_ids() {
+        let loop_net = || net(vec![vec![1]], vec![vec![1]]);
+        let first =
+            start_simulation_step(None, vec![1], loop_net(), SimulationOptions::default()).unwrap();
+        let claimed = start_simulation_step(
+            Some(first.session + 1000),
+            vec![1],
+            loop_net(),
+            SimulationOptions::default(),
+        )
+        .unwrap();
+        assert_ne!(claimed.session, first.session + 1000);
+
+        let restarted = start_simulation_step(
+            Some(first.session),
+            vec![1],
+            loop_net(),
+            SimulationOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(restarted.session, first.session);
+    }
+
+    #[test]
+    fn failed_sessions_are_dropped() {
+        let first = start_simulation_step(
+            None,
+            vec![1],
+            net(vec![vec![1]], vec![vec![1]]),
+            SimulationOptions::default(),
+        )
+        .unwrap();
+        let second = start_simulation_step(
+            None,
+            vec![1],
+            net(vec![vec![1]], vec![vec![1]]),
+            SimulationOptions::default(),
+        )
+        .unwrap();
+
+        // poison the lock of the first session like a panicking simulation would
+        let state = sessions().get(first.session).unwrap();
+        let failed = std::thread::spawn(move || {
+            let _state = state.lock().unwrap();
+            panic!("simulation failed");
+        })
+        .join();
+        assert!(failed.is_err());
+
+        let limit = || SimulationLimit::new(Some(10), None);
+        assert!(continue_simulation(first.session, limit()).is_err());
+        assert!(!drop_simulation(first.session).unwrap());
+        assert!(continue_simulation(second.session, limit()).is_ok());
+        assert!(list_simulations().is_ok());
+    }
+}
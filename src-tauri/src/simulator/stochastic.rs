@@ -0,0 +1,88 @@
+use rand::distributions::WeightedIndex;
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::common::{InputState, StochasticTransition};
+
+/// Checks that the stochastic parameters match the net and are positive.
+pub(super) fn validate_stochastic_transitions(
+    transitions: &[StochasticTransition],
+    transition_count: usize,
+) -> Result<(), String> {
+    if transitions.len() != transition_count {
+        return Err(format!(
+            "❌Expected stochastic parameters for {} transitions, but got {}!",
+            transition_count,
+            transitions.len()
+        ));
+    }
+
+    for (t, transition) in transitions.iter().enumerate() {
+        let valid = match transition {
+            StochasticTransition::Timed { rate } => rate.is_finite() && *rate > 0.0,
+            StochasticTransition::Immediate { weight, .. } => weight.is_finite() && *weight > 0.0,
+        };
+        if !valid {
+            return Err(format!(
+                "❌Transition {} needs a positive rate or weight!",
+                t
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Selects the next transition according to the GSPN semantics and returns it with the elapsed model time.
+///
+/// Enabled immediate transitions always fire first without consuming time. Among them, only the ones with the
+/// highest priority are considered and one is chosen according to their weights.
+/// Otherwise, all enabled timed transitions race against each other with exponentially distributed delays.
+/// The race is sampled directly: the delay of the race is exponential with the sum of all rates and each
+/// transition wins with a probability proportional to its rate.
+pub(super) fn select_stochastic_transition(
+    active_transitions: &InputState,
+    transitions: &[StochasticTransition],
+    rng: &mut StdRng,
+) -> (usize, f64) {
+    let highest_priority = active_transitions
+        .iter()
+        .filter_map(|&t| match transitions[t as usize] {
+            StochasticTransition::Immediate { priority, .. } => Some(priority),
+            StochasticTransition::Timed { .. } => None,
+        })
+        .max();
+
+    if let Some(highest_priority) = highest_priority {
+        let candidates: Vec<(usize, f64)> = active_transitions
+            .iter()
+            .filter_map(|&t| match transitions[t as usize] {
+                StochasticTransition::Immediate { weight, priority }
+                    if priority == highest_priority =>
+                {
+                    Some((t as usize, weight))
+                }
+                _ => None,
+            })
+            .collect();
+        return (select_weighted(&candidates, rng), 0.0);
+    }
+
+    let candidates: Vec<(usize, f64)> = active_transitions
+        .iter()
+        .filter_map(|&t| match transitions[t as usize] {
+            StochasticTransition::Timed { rate } => Some((t as usize, rate)),
+            StochasticTransition::Immediate { .. } => None,
+        })
+        .collect();
+    let total_rate: f64 = candidates.iter().map(|(_, rate)| rate).sum();
+    // gen() samples from [0, 1), so the logarithm is always finite
+    let delay = -(1.0 - rng.gen::<f64>()).ln() / total_rate;
+
+    return (select_weighted(&candidates, rng), delay);
+}
+
+pub(super) fn select_weighted(candidates: &[(usize, f64)], rng: &mut StdRng) -> usize {
+    let distribution = WeightedIndex::new(candidates.iter().map(|(_, weight)| weight)).unwrap();
+    return candidates[rng.sample(distribution)].0;
+}
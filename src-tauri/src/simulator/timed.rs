@@ -0,0 +1,255 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::common::*;
+use crate::expression::Expression;
+use crate::simulator::conflict::ConflictResolver;
+use crate::simulator::SimulationLimit;
+
+/// A run stops as Zeno behaviour once this many firings have started without any model time passing.
+const MAX_FIRINGS_WITHOUT_TIME: usize = 10000;
+
+/// The discrete-event engine of a timed simulation session.
+///
+/// A transition starts firing as soon as it is enabled and consumes its input tokens immediately.
+/// Its output tokens are produced when its firing delay has passed. Until then, the completion is kept in the event
+/// queue, so that multiple transitions (or the same transition multiple times) can fire concurrently.
+/// The tokens that pending firings will produce count towards the capacities of their places, so that completions
+/// never exceed a capacity.
+pub(super) struct TimedSimulation {
+    delays: Vec<FiringDelay>,
+    pending: BinaryHeap<Reverse<PendingCompletion>>,
+    scheduled: u64,
+    /// The tokens that the pending firings will produce, per place
+    producing: State,
+    /// The firings that started since model time last passed, which is also counted across requests
+    firings_without_time: usize,
+}
+
+/// A transition that is currently firing and completes at the given model time
+struct PendingCompletion {
+    time: f64,
+    /// Orders completions with the same time by the order in which they were scheduled.
+    sequence: u64,
+    transition: usize,
+}
+
+impl PartialEq for PendingCompletion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PendingCompletion {}
+
+impl PartialOrd for PendingCompletion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PendingCompletion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.time
+            .total_cmp(&other.time)
+            .then(self.sequence.cmp(&other.sequence))
+    }
+}
+
+/// The outcome of running the event engine
+pub(super) struct TimedRun {
+    pub(super) steps: usize,
+    pub(super) deadlocked: bool,
+    /// Whether the run ended because the marking satisfies the stop condition.
+    pub(super) stopped: bool,
+    /// Whether the run ended because transitions with zero delay kept firing without model time passing.
+    pub(super) zeno: bool,
+    pub(super) events: Vec<TimedEvent>,
+}
+
+/// Checks that the firing delays match the net and are not negative.
+pub(super) fn validate_firing_delays(
+    delays: &[FiringDelay],
+    transition_count: usize,
+) -> Result<(), String> {
+    if delays.len() != transition_count {
+        return Err(format!(
+            "❌Expected firing delays for {} transitions, but got {}!",
+            transition_count,
+            delays.len()
+        ));
+    }
+
+    for (t, delay) in delays.iter().enumerate() {
+        let valid = match *delay {
+            FiringDelay::Deterministic { delay } => delay.is_finite() && delay >= 0.0,
+            FiringDelay::Interval { min, max } => {
+                min.is_finite() && max.is_finite() && min >= 0.0 && min <= max
+            }
+        };
+        if !valid {
+            return Err(format!("❌Transition {} has an invalid firing delay!", t));
+        }
+    }
+
+    Ok(())
+}
+
+impl TimedSimulation {
+    pub(super) fn new(delays: Vec<FiringDelay>, place_count: usize) -> Self {
+        TimedSimulation {
+            delays,
+            pending: BinaryHeap::new(),
+            scheduled: 0,
+            producing: State::zeros(place_count),
+            firings_without_time: 0,
+        }
+    }
+
+    /// Runs the event loop until the limit is reached, the stop condition holds after firings complete, or no
+    /// transition is enabled and no firing is pending.
+    /// The marking is reported whenever firings complete, which are the only points where model time passes.
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn run(
+        &mut self,
+        state: &mut State,
+        net: &Net,
+        limit: SimulationLimit,
+        resolver: &mut ConflictResolver,
+        rng: &mut StdRng,
+        model_time: &mut f64,
+        stop_condition: Option<&Expression>,
+        mut on_fire: impl FnMut(usize, &State),
+    ) -> TimedRun {
+        let start = Instant::now();
+        let mut steps: usize = 0;
+        let mut events = Vec::new();
+        let stops =
+            |state: &State| stop_condition.is_some_and(|condition| condition.holds(state, net));
+
+        while !limit.is_reached(&start, steps) {
+            let active_transitions: InputState = find_active_transitions(state, net)
+                .into_iter()
+                .filter(|&t| self.has_capacity(state, net, t as usize))
+                .collect();
+            if !active_transitions.is_empty() {
+                if self.firings_without_time >= MAX_FIRINGS_WITHOUT_TIME {
+                    println!(
+                        "♾️{} transitions fired at model time {} without time passing.",
+                        self.firings_without_time, model_time
+                    );
+                    return TimedRun {
+                        steps,
+                        deadlocked: false,
+                        stopped: false,
+                        zeno: true,
+                        events,
+                    };
+                }
+                self.firings_without_time += 1;
+                let fired = resolver.select(&active_transitions, net, rng);
+                *state = consume_tokens(state, net, fired);
+                self.schedule(fired, net, *model_time, rng);
+                on_fire(fired, state);
+                steps += 1;
+                // the stop condition is only checked once firings complete, because the marking misses the tokens of
+                // the started firings until then
+                continue;
+            }
+
+            let Some(Reverse(completion)) = self.pending.pop() else {
+                println!(
+                    "☠️No active or pending transitions at model time {} with state {:?}.",
+                    model_time, state
+                );
+                return TimedRun {
+                    steps,
+                    deadlocked: true,
+                    stopped: false,
+                    zeno: false,
+                    events,
+                };
+            };
+            // complete all firings that end at the same time before new transitions may start
+            if completion.time > *model_time {
+                self.firings_without_time = 0;
+            }
+            *model_time = completion.time;
+            self.complete(state, net, completion.transition);
+            while self
+                .pending
+                .peek()
+                .is_some_and(|Reverse(next)| next.time == completion.time)
+            {
+                let Reverse(simultaneous) = self.pending.pop().unwrap();
+                self.complete(state, net, simultaneous.transition);
+            }
+            events.push(TimedEvent::new(completion.time, state.to_vec()));
+            if stops(state) {
+                return TimedRun {
+                    steps,
+                    deadlocked: false,
+                    stopped: true,
+                    zeno: false,
+                    events,
+                };
+            }
+
+            // check if the marking is close to overflow
+            if state.iter().max().unwrap() > &30000 {
+                println!("⚠️State {:?} is close to integer overflow. Marking simulation as deadlocked to prevent system panic.", state);
+                return TimedRun {
+                    steps,
+                    deadlocked: true,
+                    stopped: false,
+                    zeno: false,
+                    events,
+                };
+            }
+        }
+
+        return TimedRun {
+            steps,
+            deadlocked: false,
+            stopped: false,
+            zeno: false,
+            events,
+        };
+    }
+
+    /// Checks that starting the transition keeps every place within its capacity, even after all pending firings
+    /// have completed.
+    fn has_capacity(&self, state: &State, net: &Net, t: usize) -> bool {
+        let Some(capacities) = &net.capacities else {
+            return true;
+        };
+        let completed = produce_tokens(&consume_tokens(state, net, t), net, t) + &self.producing;
+        return completed
+            .iter()
+            .zip(capacities.iter())
+            .all(|(&tokens, capacity)| capacity.is_none_or(|c| tokens <= c));
+    }
+
+    fn complete(&mut self, state: &mut State, net: &Net, transition: usize) {
+        *state = produce_tokens(state, net, transition);
+        self.producing -= &net.t_out.row(transition);
+    }
+
+    fn schedule(&mut self, transition: usize, net: &Net, now: f64, rng: &mut StdRng) {
+        let delay = match self.delays[transition] {
+            FiringDelay::Deterministic { delay } => delay,
+            FiringDelay::Interval { min, max } => rng.gen_range(min..=max),
+        };
+        self.scheduled += 1;
+        self.producing += &net.t_out.row(transition);
+        self.pending.push(Reverse(PendingCompletion {
+            time: now + delay,
+            sequence: self.scheduled,
+            transition,
+        }));
+    }
+}
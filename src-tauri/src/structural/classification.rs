@@ -0,0 +1,140 @@
+use std::collections::BTreeSet;
+
+use crate::common::*;
+
+/// The pre- and post-sets of all places and transitions, ignoring the arc weights
+struct Structure {
+    pre_transition: Vec<BTreeSet<usize>>,
+    post_transition: Vec<BTreeSet<usize>>,
+    pre_place: Vec<BTreeSet<usize>>,
+    post_place: Vec<BTreeSet<usize>>,
+}
+
+impl Structure {
+    fn new(net: &Net) -> Self {
+        let (transitions, places) = (net.transition_count(), net.place_count());
+        let mut structure = Structure {
+            pre_transition: vec![BTreeSet::new(); transitions],
+            post_transition: vec![BTreeSet::new(); transitions],
+            pre_place: vec![BTreeSet::new(); places],
+            post_place: vec![BTreeSet::new(); places],
+        };
+        for t in 0..transitions {
+            for p in 0..places {
+                if net.t_in[[t, p]] > 0 {
+                    structure.pre_transition[t].insert(p);
+                    structure.post_place[p].insert(t);
+                }
+                if net.t_out[[t, p]] > 0 {
+                    structure.post_transition[t].insert(p);
+                    structure.pre_place[p].insert(t);
+                }
+            }
+        }
+        structure
+    }
+}
+
+/// Decides to which syntactic subclasses the net belongs. Only the input and output arcs are considered. The
+/// subclasses are defined for ordinary nets, so transitions with weighted arcs violate each of them.
+pub(super) fn classify(net: &Net) -> NetClassification {
+    let structure = Structure::new(net);
+    let ordinary = ordinary(net);
+    let weighted = &ordinary.offending_transitions;
+    let state_machine = with_weighted(state_machine(&structure), weighted);
+    let marked_graph = with_weighted(marked_graph(&structure), weighted);
+    let free_choice = with_weighted(free_choice(&structure), weighted);
+    let extended_free_choice = with_weighted(shared_choice(&structure, |a, b| a == b), weighted);
+    let asymmetric_choice = with_weighted(
+        shared_choice(&structure, |a, b| a.is_subset(b) || b.is_subset(a)),
+        weighted,
+    );
+
+    return NetClassification::new(
+        ordinary,
+        state_machine,
+        marked_graph,
+        free_choice,
+        extended_free_choice,
+        asymmetric_choice,
+    );
+}
+
+/// Adds the transitions with weighted arcs to the transitions that violate a subclass.
+fn with_weighted(membership: ClassMembership, weighted: &[usize]) -> ClassMembership {
+    let mut offending: BTreeSet<usize> = membership.offending_transitions.into_iter().collect();
+    offending.extend(weighted);
+    return ClassMembership::new(membership.offending_places, offending.into_iter().collect());
+}
+
+/// Every arc has the weight 1.
+fn ordinary(net: &Net) -> ClassMembership {
+    let offending = (0..net.transition_count())
+        .filter(|&t| {
+            let (inputs, outputs) = (net.t_in.row(t), net.t_out.row(t));
+            inputs
+                .iter()
+                .chain(outputs.iter())
+                .any(|&weight| weight > 1)
+        })
+        .collect();
+    return ClassMembership::new(Vec::new(), offending);
+}
+
+/// Every transition has exactly one input and one output place.
+fn state_machine(structure: &Structure) -> ClassMembership {
+    let offending = (0..structure.pre_transition.len())
+        .filter(|&t| {
+            structure.pre_transition[t].len() != 1 || structure.post_transition[t].len() != 1
+        })
+        .collect();
+    return ClassMembership::new(Vec::new(), offending);
+}
+
+/// Every place has exactly one input and one output transition.
+fn marked_graph(structure: &Structure) -> ClassMembership {
+    let offending = (0..structure.pre_place.len())
+        .filter(|&p| structure.pre_place[p].len() != 1 || structure.post_place[p].len() != 1)
+        .collect();
+    return ClassMembership::new(offending, Vec::new());
+}
+
+/// Every place with multiple output transitions is the only input place of all of them.
+fn free_choice(structure: &Structure) -> ClassMembership {
+    let mut offending_places = BTreeSet::new();
+    let mut offending_transitions = BTreeSet::new();
+    for (p, post) in structure.post_place.iter().enumerate() {
+        if post.len() < 2 {
+            continue;
+        }
+        for &t in post.iter() {
+            if structure.pre_transition[t].len() > 1 {
+                offending_places.insert(p);
+                offending_transitions.insert(t);
+            }
+        }
+    }
+    return ClassMembership::new(
+        offending_places.into_iter().collect(),
+        offending_transitions.into_iter().collect(),
+    );
+}
+
+/// All places that share an output transition need to have related post-sets. With equal post-sets, the net is
+/// extended free-choice. With post-sets that contain each other, it is asymmetric choice.
+fn shared_choice(
+    structure: &Structure,
+    related: impl Fn(&BTreeSet<usize>, &BTreeSet<usize>) -> bool,
+) -> ClassMembership {
+    let post = &structure.post_place;
+    let mut offending_places = BTreeSet::new();
+    for p in 0..post.len() {
+        for q in (p + 1)..post.len() {
+            if !post[p].is_disjoint(&post[q]) && !related(&post[p], &post[q]) {
+                offending_places.insert(p);
+                offending_places.insert(q);
+            }
+        }
+    }
+    return ClassMembership::new(offending_places.into_iter().collect(), Vec::new());
+}
@@ -0,0 +1,213 @@
+use crate::common::*;
+
+/// A row of the Farkas table: the part of the matrix that is not yet eliminated and the combination of variables
+struct FarkasRow {
+    remaining: Vec<i64>,
+    combination: Vec<i64>,
+}
+
+impl FarkasRow {
+    fn combine(positive: &FarkasRow, negative: &FarkasRow, column: usize) -> Self {
+        let a = -negative.remaining[column];
+        let b = positive.remaining[column];
+        let mut row = FarkasRow {
+            remaining: linear_combination(&positive.remaining, a, &negative.remaining, b),
+            combination: linear_combination(&positive.combination, a, &negative.combination, b),
+        };
+        row.normalize();
+        row
+    }
+
+    fn normalize(&mut self) {
+        let divisor = self
+            .remaining
+            .iter()
+            .chain(self.combination.iter())
+            .fold(0, |divisor, &value| gcd(divisor, value.abs()));
+        if divisor > 1 {
+            self.remaining
+                .iter_mut()
+                .for_each(|value| *value /= divisor);
+            self.combination
+                .iter_mut()
+                .for_each(|value| *value /= divisor);
+        }
+    }
+
+    fn support(&self) -> Vec<bool> {
+        self.combination.iter().map(|&value| value != 0).collect()
+    }
+}
+
+/// Computes a minimal generating set of the non-negative integer vectors x with x·A = 0 with the Farkas algorithm.
+/// The matrix A has one row per variable. Every returned vector has a minimal support.
+pub(super) fn farkas(matrix: &[Vec<i64>]) -> Vec<Vec<i64>> {
+    let variables = matrix.len();
+    let columns = matrix.first().map_or(0, |row| row.len());
+
+    let mut table: Vec<FarkasRow> = matrix
+        .iter()
+        .enumerate()
+        .map(|(i, row)| FarkasRow {
+            remaining: row.clone(),
+            combination: (0..variables).map(|j| if i == j { 1 } else { 0 }).collect(),
+        })
+        .collect();
+
+    for column in 0..columns {
+        let mut next: Vec<FarkasRow> = Vec::new();
+        let (zero, non_zero): (Vec<FarkasRow>, Vec<FarkasRow>) = table
+            .into_iter()
+            .partition(|row| row.remaining[column] == 0);
+        let (positive, negative): (Vec<FarkasRow>, Vec<FarkasRow>) = non_zero
+            .into_iter()
+            .partition(|row| row.remaining[column] > 0);
+
+        for p in positive.iter() {
+            for n in negative.iter() {
+                next.push(FarkasRow::combine(p, n, column));
+            }
+        }
+        next.extend(zero);
+        table = remove_non_minimal(next);
+    }
+
+    return table.into_iter().map(|row| row.combination).collect();
+}
+
+/// Removes all rows whose support strictly contains the support of another row, and duplicates.
+fn remove_non_minimal(rows: Vec<FarkasRow>) -> Vec<FarkasRow> {
+    let supports: Vec<Vec<bool>> = rows.iter().map(FarkasRow::support).collect();
+    let mut minimal: Vec<FarkasRow> = Vec::new();
+    for (i, row) in rows.into_iter().enumerate() {
+        let dominated = supports
+            .iter()
+            .enumerate()
+            .any(|(j, other)| j != i && is_strict_subset(other, &supports[i]));
+        let duplicate = minimal
+            .iter()
+            .any(|kept| kept.combination == row.combination);
+        if !dominated && !duplicate {
+            minimal.push(row);
+        }
+    }
+    return minimal;
+}
+
+fn is_strict_subset(subset: &[bool], superset: &[bool]) -> bool {
+    let contained = subset.iter().zip(superset.iter()).all(|(&a, &b)| !a || b);
+    contained && subset != superset
+}
+
+fn linear_combination(x: &[i64], a: i64, y: &[i64], b: i64) -> Vec<i64> {
+    x.iter()
+        .zip(y.iter())
+        .map(|(&x, &y)| a * x + b * y)
+        .collect()
+}
+
+pub(super) fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Computes the minimal P-invariants of the net, with one weight per place.
+///
+/// A P-invariant weights the places so that no transition changes the weighted token sum. Reset arcs can only
+/// remove tokens, so the sum can still decrease in nets with reset arcs.
+pub(super) fn p_invariants(marking: &InputState, net: &Net) -> Vec<PInvariant> {
+    // one row per place, one column per transition
+    let incidence: Vec<Vec<i64>> = net
+        .t_effect
+        .columns()
+        .into_iter()
+        .map(|column| column.iter().map(|&effect| effect as i64).collect())
+        .collect();
+
+    return farkas(&incidence)
+        .into_iter()
+        .map(|weights| {
+            let token_sum = weights
+                .iter()
+                .zip(marking.iter())
+                .map(|(&weight, &tokens)| weight * tokens as i64)
+                .sum();
+            PInvariant::new(weights, token_sum)
+        })
+        .collect();
+}
+
+/// Computes the minimal T-invariants of the net, with one weight per transition.
+///
+/// Firing every transition as often as its weight reproduces the marking, if the firing sequence is possible.
+pub(super) fn t_invariants(net: &Net) -> Vec<Vec<i64>> {
+    return farkas(&incidence_per_transition(net));
+}
+
+/// Checks whether every transition is covered by a T-invariant, so that there is a firing count vector with only
+/// positive entries that reproduces any marking.
+pub(super) fn is_consistent(t_invariants: &[Vec<i64>], net: &Net) -> bool {
+    return covers_all(t_invariants, net.transition_count());
+}
+
+/// Checks whether every place is covered by a P-invariant, so that the weighted token sum of all places is constant.
+pub(super) fn is_conservative(p_invariants: &[PInvariant], net: &Net) -> bool {
+    let weights: Vec<Vec<i64>> = p_invariants
+        .iter()
+        .map(|invariant| invariant.weights.clone())
+        .collect();
+    return covers_all(&weights, net.place_count());
+}
+
+/// Checks whether there is a firing count vector with only positive entries that does not decrease any marking.
+///
+/// The additional slack variables s with `C·x - s = 0` turn the inequality into an equation, so the Farkas algorithm
+/// can solve it. Every transition needs to be covered by a solution, because their sum is a solution as well.
+pub(super) fn is_repetitive(net: &Net) -> bool {
+    let places = net.place_count();
+    let mut matrix = incidence_per_transition(net);
+    matrix.extend((0..places).map(|p| (0..places).map(|i| if i == p { -1 } else { 0 }).collect()));
+
+    let solutions: Vec<Vec<i64>> = farkas(&matrix)
+        .into_iter()
+        .map(|solution| solution[..net.transition_count()].to_vec())
+        .collect();
+    return covers_all(&solutions, net.transition_count());
+}
+
+fn covers_all(vectors: &[Vec<i64>], length: usize) -> bool {
+    return (0..length).all(|i| vectors.iter().any(|vector| vector[i] > 0));
+}
+
+/// One row per transition, one column per place
+pub(super) fn incidence_per_transition(net: &Net) -> Vec<Vec<i64>> {
+    return net
+        .t_effect
+        .rows()
+        .into_iter()
+        .map(|row| row.iter().map(|&effect| effect as i64).collect())
+        .collect();
+}
+
+/// Derives an upper bound for every place that is covered by a P-invariant or limited by a capacity.
+///
+/// The tokens of a place can never exceed the token sum of an invariant divided by the weight of the place.
+pub(super) fn place_bounds(invariants: &[PInvariant], net: &Net) -> Vec<Option<i64>> {
+    return (0..net.place_count())
+        .map(|p| {
+            let invariant_bound = invariants
+                .iter()
+                .filter(|invariant| invariant.weights[p] > 0)
+                .map(|invariant| invariant.token_sum / invariant.weights[p])
+                .min();
+            let capacity = net.capacity(p).map(|capacity| capacity as i64);
+            match (invariant_bound, capacity) {
+                (Some(bound), Some(capacity)) => Some(bound.min(capacity)),
+                (bound, capacity) => bound.or(capacity),
+            }
+        })
+        .collect();
+}
@@ -0,0 +1,60 @@
+use std::time::Instant;
+
+use crate::common::*;
+
+mod classification;
+mod invariants;
+mod mod_test;
+mod rank_theorem;
+mod siphons;
+
+/// Analyzes the structure of the net without exploring its state space.
+pub fn analyze_structure(marking: InputState, net: Net) -> Result<StructuralResponse, String> {
+    net.check_marking(&marking)?;
+    let start_time = Instant::now();
+
+    let p_invariants = invariants::p_invariants(&marking, &net);
+    let place_bounds = invariants::place_bounds(&p_invariants, &net);
+    let t_invariants = invariants::t_invariants(&net);
+    let consistent = invariants::is_consistent(&t_invariants, &net);
+    let repetitive = invariants::is_repetitive(&net);
+    let classification = classification::classify(&net);
+    let is_free_choice = classification.free_choice.member && net.is_plain();
+    let siphons: Vec<Siphon> = siphons::minimal_siphons(&marking, &net)
+        .into_iter()
+        .map(|siphon| {
+            let can_be_emptied = is_free_choice.then_some(!siphon.has_marked_trap);
+            siphon.with_can_be_emptied(can_be_emptied)
+        })
+        .collect();
+    let siphons_contain_marked_traps = siphons.iter().all(|siphon| siphon.has_marked_trap);
+    let maximal_trap = siphons::net_maximal_trap(&net);
+    let free_choice = is_free_choice.then(|| {
+        rank_theorem::check_free_choice(
+            &net,
+            consistent,
+            invariants::is_conservative(&p_invariants, &net),
+            siphons_contain_marked_traps,
+        )
+    });
+
+    println!(
+        "📐Structural analysis found {} P-invariants and {} T-invariants and took {}ms",
+        p_invariants.len(),
+        t_invariants.len(),
+        start_time.elapsed().as_millis()
+    );
+
+    return Ok(StructuralResponse {
+        p_invariants,
+        place_bounds,
+        t_invariants,
+        consistent,
+        repetitive,
+        siphons,
+        siphons_contain_marked_traps,
+        maximal_trap,
+        classification,
+        free_choice,
+    });
+}
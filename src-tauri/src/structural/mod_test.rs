@@ -0,0 +1,384 @@
+#[cfg(test)]
+mod tests {
+    use crate::common::{InputMatrix, InputState, Net, StructuralResponse};
+
+    fn analyze_structure(
+        marking: InputState,
+        transition_inputs: InputMatrix,
+        transition_outputs: InputMatrix,
+    ) -> StructuralResponse {
+        let net = Net::new(&transition_inputs, &transition_outputs).unwrap();
+        crate::structural::analyze_structure(marking, net).unwrap()
+    }
+
+    fn weights(result: &StructuralResponse) -> Vec<Vec<i64>> {
+        let mut weights: Vec<Vec<i64>> = result
+            .p_invariants
+            .iter()
+            .map(|invariant| invariant.weights.clone())
+            .collect();
+        weights.sort();
+        weights
+    }
+
+    #[test]
+    fn circle_is_covered_by_invariant() {
+        // Circle   ┌──>███─>(1)
+        //         (0)<─███<──┘
+        let result = analyze_structure(
+            vec![0, 1],
+            vec![vec![0, 1], vec![1, 0]],
+            vec![vec![1, 0], vec![0, 1]],
+        );
+
+        assert_eq!(weights(&result), vec![vec![1, 1]]);
+        assert_eq!(result.p_invariants[0].token_sum, 1);
+        assert_eq!(result.place_bounds, vec![Some(1), Some(1)]);
+    }
+
+    #[test]
+    fn mutual_exclusion_invariants() {
+        // Two processes (idle, critical) share a mutex: places idle1, crit1, idle2, crit2, mutex
+        let result = analyze_structure(
+            vec![1, 0, 1, 0, 1],
+            vec![
+                vec![1, 0, 0, 0, 1],
+                vec![0, 1, 0, 0, 0],
+                vec![0, 0, 1, 0, 1],
+                vec![0, 0, 0, 1, 0],
+            ],
+            vec![
+                vec![0, 1, 0, 0, 0],
+                vec![1, 0, 0, 0, 1],
+                vec![0, 0, 0, 1, 0],
+                vec![0, 0, 1, 0, 1],
+            ],
+        );
+
+        assert_eq!(
+            weights(&result),
+            vec![
+                vec![0, 0, 1, 1, 0],
+                vec![0, 1, 0, 1, 1],
+                vec![1, 1, 0, 0, 0],
+            ]
+        );
+        assert!(result
+            .p_invariants
+            .iter()
+            .all(|invariant| invariant.token_sum == 1));
+        assert_eq!(result.place_bounds, vec![Some(1); 5]);
+    }
+
+    #[test]
+    fn weighted_invariant() {
+        // Two tokens in the first place become one token in the second place and back
+        // (2)─2─>███──>(0)
+        //  └──<2─███<──┘
+        let result = analyze_structure(
+            vec![4, 0],
+            vec![vec![2, 0], vec![0, 1]],
+            vec![vec![0, 1], vec![2, 0]],
+        );
+
+        assert_eq!(weights(&result), vec![vec![1, 2]]);
+        assert_eq!(result.p_invariants[0].token_sum, 4);
+        assert_eq!(result.place_bounds, vec![Some(4), Some(2)]);
+    }
+
+    #[test]
+    fn producer_has_no_bound() {
+        // Unbounded net ███──>(1), once without and once with a capacity
+        let result = analyze_structure(vec![1], vec![vec![0]], vec![vec![1]]);
+        assert!(result.p_invariants.is_empty());
+        assert_eq!(result.place_bounds, vec![None]);
+
+        let net = Net::new(&vec![vec![0]], &vec![vec![1]])
+            .and_then(|net| net.with_capacities(Some(vec![Some(5)])))
+            .unwrap();
+        let result = crate::structural::analyze_structure(vec![1], net).unwrap();
+        assert_eq!(result.place_bounds, vec![Some(5)]);
+    }
+
+    #[test]
+    fn circle_is_consistent() {
+        // Circle   ┌──>███─>(1)
+        //         (0)<─███<──┘
+        let result = analyze_structure(
+            vec![0, 1],
+            vec![vec![0, 1], vec![1, 0]],
+            vec![vec![1, 0], vec![0, 1]],
+        );
+
+        assert_eq!(result.t_invariants, vec![vec![1, 1]]);
+        assert!(result.consistent);
+        assert!(result.repetitive);
+    }
+
+    #[test]
+    fn partially_covered_transitions() {
+        // A circle and a transition that only produces tokens
+        //  ┌──>███─>(0)   ███──>(0)
+        // (1)<─███<──┘
+        let result = analyze_structure(
+            vec![0, 1, 0],
+            vec![vec![0, 1, 0], vec![1, 0, 0], vec![0, 0, 0]],
+            vec![vec![1, 0, 0], vec![0, 1, 0], vec![0, 0, 1]],
+        );
+
+        assert_eq!(result.t_invariants, vec![vec![1, 1, 0]]);
+        assert!(!result.consistent);
+        assert!(result.repetitive);
+    }
+
+    #[test]
+    fn consumer_is_not_repetitive() {
+        // (1)──>███
+        let result = analyze_structure(vec![1], vec![vec![1]], vec![vec![0]]);
+
+        assert!(result.t_invariants.is_empty());
+        assert!(!result.consistent);
+        assert!(!result.repetitive);
+    }
+
+    fn mutual_exclusion(marking: InputState) -> StructuralResponse {
+        // Two processes (idle, critical) share a mutex: places idle1, crit1, idle2, crit2, mutex
+        analyze_structure(
+            marking,
+            vec![
+                vec![1, 0, 0, 0, 1],
+                vec![0, 1, 0, 0, 0],
+                vec![0, 0, 1, 0, 1],
+                vec![0, 0, 0, 1, 0],
+            ],
+            vec![
+                vec![0, 1, 0, 0, 0],
+                vec![1, 0, 0, 0, 1],
+                vec![0, 0, 0, 1, 0],
+                vec![0, 0, 1, 0, 1],
+            ],
+        )
+    }
+
+    #[test]
+    fn siphons_of_mutual_exclusion() {
+        let result = mutual_exclusion(vec![1, 0, 1, 0, 1]);
+        let siphons: Vec<Vec<usize>> = result.siphons.iter().map(|s| s.places.clone()).collect();
+
+        // the siphons are the supports of the P-invariants
+        assert_eq!(siphons, vec![vec![0, 1], vec![2, 3], vec![1, 3, 4]]);
+        assert!(result.siphons_contain_marked_traps);
+        assert_eq!(result.maximal_trap, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn unmarked_siphon_has_no_marked_trap() {
+        // without the mutex token, both processes are stuck
+        let result = mutual_exclusion(vec![1, 0, 1, 0, 0]);
+        let unprotected: Vec<Vec<usize>> = result
+            .siphons
+            .iter()
+            .filter(|siphon| !siphon.has_marked_trap)
+            .map(|siphon| siphon.places.clone())
+            .collect();
+
+        assert_eq!(unprotected, vec![vec![1, 3, 4]]);
+        assert!(!result.siphons_contain_marked_traps);
+        // the processes compete for the mutex, so the net is not free-choice and emptiability is not decided
+        assert!(result
+            .siphons
+            .iter()
+            .all(|siphon| siphon.can_be_emptied.is_none()));
+    }
+
+    #[test]
+    fn consumed_siphon_without_trap() {
+        // (1)──>███──>(0)   the first place is a siphon that contains no trap
+        let result = analyze_structure(vec![1, 0], vec![vec![1, 0]], vec![vec![0, 1]]);
+
+        assert_eq!(result.siphons.len(), 1);
+        assert_eq!(result.siphons[0].places, vec![0]);
+        assert!(result.siphons[0].maximal_trap.is_empty());
+        assert!(!result.siphons[0].has_marked_trap);
+        assert_eq!(result.maximal_trap, vec![0, 1]);
+    }
+
+    #[test]
+    fn free_choice_siphons_without_marked_trap_can_be_emptied() {
+        // (1)──>███──>(0)   the free-choice net empties the siphon of the first place
+        let consumed = analyze_structure(vec![1, 0], vec![vec![1, 0]], vec![vec![0, 1]]);
+        assert_eq!(consumed.siphons[0].can_be_emptied, Some(true));
+
+        //  ┌──>███──┐
+        // (1)       (0)   the token circles forever in the siphon, which is a marked trap
+        //  └──███<──┘
+        let circle = analyze_structure(
+            vec![1, 0],
+            vec![vec![1, 0], vec![0, 1]],
+            vec![vec![0, 1], vec![1, 0]],
+        );
+        assert_eq!(circle.siphons.len(), 1);
+        assert!(circle.siphons[0].has_marked_trap);
+        assert_eq!(circle.siphons[0].can_be_emptied, Some(false));
+    }
+
+    #[test]
+    fn circle_is_state_machine_and_marked_graph() {
+        // Circle   ┌──>███─>(1)
+        //         (0)<─███<──┘
+        let result = analyze_structure(
+            vec![0, 1],
+            vec![vec![0, 1], vec![1, 0]],
+            vec![vec![1, 0], vec![0, 1]],
+        );
+        let classes = &result.classification;
+
+        assert!(classes.ordinary.member);
+        assert!(classes.state_machine.member);
+        assert!(classes.marked_graph.member);
+        assert!(classes.free_choice.member);
+        assert!(classes.extended_free_choice.member);
+        assert!(classes.asymmetric_choice.member);
+    }
+
+    #[test]
+    fn mutual_exclusion_is_asymmetric_choice() {
+        let result = mutual_exclusion(vec![1, 0, 1, 0, 1]);
+        let classes = &result.classification;
+
+        assert!(!classes.state_machine.member);
+        assert_eq!(
+            classes.state_machine.offending_transitions,
+            vec![0, 1, 2, 3]
+        );
+        assert_eq!(classes.marked_graph.offending_places, vec![4]);
+        // the mutex is shared by the transitions that enter the critical sections
+        assert_eq!(classes.free_choice.offending_places, vec![4]);
+        assert_eq!(classes.free_choice.offending_transitions, vec![0, 2]);
+        assert_eq!(classes.extended_free_choice.offending_places, vec![0, 2, 4]);
+        assert!(classes.asymmetric_choice.member);
+    }
+
+    #[test]
+    fn confusion_is_not_asymmetric_choice() {
+        // Two places share a transition, but each also has its own one
+        // (1)──>███   (1)──>███
+        //  └───>███<───┘
+        let result = analyze_structure(
+            vec![1, 1],
+            vec![vec![1, 0], vec![1, 1], vec![0, 1]],
+            vec![vec![0, 0], vec![0, 0], vec![0, 0]],
+        );
+        let classes = &result.classification;
+
+        assert_eq!(classes.asymmetric_choice.offending_places, vec![0, 1]);
+        assert!(!classes.extended_free_choice.member);
+    }
+
+    #[test]
+    fn weighted_arcs_are_not_ordinary() {
+        let result = analyze_structure(vec![2], vec![vec![2]], vec![vec![1]]);
+
+        let classes = &result.classification;
+
+        assert_eq!(classes.ordinary.offending_transitions, vec![0]);
+        // the subclasses are only defined for ordinary nets
+        for class in [
+            &classes.state_machine,
+            &classes.marked_graph,
+            &classes.free_choice,
+            &classes.extended_free_choice,
+            &classes.asymmetric_choice,
+        ] {
+            assert!(!class.member);
+            assert_eq!(class.offending_transitions, vec![0]);
+        }
+    }
+
+    #[test]
+    fn weighted_arcs_join_structural_violations() {
+        // (0)─2─>███──>(1)   the first transition is weighted, the second one has two input places
+        //  └─────>███<───┘
+        let result = analyze_structure(
+            vec![2, 0],
+            vec![vec![2, 0], vec![1, 1]],
+            vec![vec![0, 1], vec![0, 0]],
+        );
+        let classes = &result.classification;
+
+        assert_eq!(classes.state_machine.offending_transitions, vec![0, 1]);
+        assert_eq!(classes.free_choice.offending_places, vec![0]);
+        assert_eq!(classes.free_choice.offending_transitions, vec![0, 1]);
+        assert_eq!(classes.marked_graph.offending_transitions, vec![0]);
+    }
+
+    #[test]
+    fn circle_is_well_formed() {
+        // Circle   ┌──>███─>(1)
+        //         (0)<─███<──┘
+        let result = analyze_structure(
+            vec![0, 1],
+            vec![vec![0, 1], vec![1, 0]],
+            vec![vec![1, 0], vec![0, 1]],
+        );
+        let analysis = result.free_choice.unwrap();
+
+        assert!(analysis.strongly_connected);
+        assert_eq!(analysis.rank, 1);
+        assert_eq!(analysis.clusters, 2);
+        assert!(analysis.well_formed);
+        assert_eq!(analysis.live, Some(true));
+        assert_eq!(analysis.bounded, Some(true));
+    }
+
+    #[test]
+    fn empty_circle_is_well_formed_but_dead() {
+        let result = analyze_structure(
+            vec![0, 0],
+            vec![vec![0, 1], vec![1, 0]],
+            vec![vec![1, 0], vec![0, 1]],
+        );
+        let analysis = result.free_choice.unwrap();
+
+        assert!(analysis.well_formed);
+        assert_eq!(analysis.live, Some(false));
+    }
+
+    #[test]
+    fn free_choice_without_rank_condition() {
+        // A choice between two transitions that both return the token, and a transition that duplicates it
+        //  ┌──>███──┐
+        // (1)       ├──>(0)──>███──┐
+        //  └──>███──┘              │
+        //  └<──────────────────────┘
+        let result = analyze_structure(
+            vec![1, 0],
+            vec![vec![1, 0], vec![1, 0], vec![0, 1]],
+            vec![vec![0, 1], vec![0, 2], vec![1, 0]],
+        );
+
+        assert!(result.free_choice.is_none());
+        assert!(!result.classification.ordinary.member);
+
+        // the same net with ordinary arcs, where the second transition also marks a third place
+        let result = analyze_structure(
+            vec![1, 0, 0],
+            vec![vec![1, 0, 0], vec![1, 0, 0], vec![0, 1, 0], vec![0, 0, 1]],
+            vec![vec![0, 1, 0], vec![0, 1, 1], vec![1, 0, 0], vec![1, 0, 0]],
+        );
+        let analysis = result.free_choice.unwrap();
+
+        assert!(analysis.strongly_connected);
+        assert!(!analysis.conservative);
+        assert!(!analysis.rank_condition);
+        assert!(!analysis.well_formed);
+        assert_eq!(analysis.live, Some(true));
+        assert_eq!(analysis.bounded, Some(false));
+    }
+
+    #[test]
+    fn mutual_exclusion_is_not_free_choice() {
+        let result = mutual_exclusion(vec![1, 0, 1, 0, 1]);
+        assert!(result.free_choice.is_none());
+    }
+}
@@ -0,0 +1,115 @@
+use petgraph::algo::kosaraju_scc;
+use petgraph::graph::DiGraph;
+use petgraph::unionfind::UnionFind;
+
+use crate::common::*;
+use crate::structural::invariants::{gcd, incidence_per_transition};
+
+/// Decides whether a free-choice net is well-formed with the rank theorem, which takes polynomial time.
+///
+/// A well-formed net has a marking that makes it live and bounded. By the rank theorem, a free-choice net is
+/// well-formed if and only if it is strongly connected, consistent, conservative and the rank of its incidence
+/// matrix is the number of its clusters minus one. Together with Commoner's theorem, this decides liveness and
+/// boundedness for the initial marking without the reachability graph.
+pub(super) fn check_free_choice(
+    net: &Net,
+    consistent: bool,
+    conservative: bool,
+    siphons_contain_marked_traps: bool,
+) -> FreeChoiceAnalysis {
+    let strongly_connected = is_strongly_connected(net);
+    let rank = rank(incidence_per_transition(net));
+    let clusters = count_clusters(net);
+    let rank_condition = rank + 1 == clusters;
+    let well_formed = strongly_connected && consistent && conservative && rank_condition;
+
+    // every live and bounded free-choice net is well-formed and therefore conservative
+    let live = siphons_contain_marked_traps;
+    let bounded = if conservative {
+        Some(true)
+    } else if live {
+        Some(false)
+    } else {
+        None
+    };
+
+    return FreeChoiceAnalysis {
+        strongly_connected,
+        consistent,
+        conservative,
+        rank,
+        clusters,
+        rank_condition,
+        well_formed,
+        live: Some(live),
+        bounded,
+    };
+}
+
+fn is_strongly_connected(net: &Net) -> bool {
+    // places are the first nodes, followed by the transitions
+    let places = net.place_count();
+    let mut graph = DiGraph::<(), ()>::new();
+    let nodes: Vec<_> = (0..places + net.transition_count())
+        .map(|_| graph.add_node(()))
+        .collect();
+    for t in 0..net.transition_count() {
+        for p in 0..places {
+            if net.t_in[[t, p]] > 0 {
+                graph.add_edge(nodes[p], nodes[places + t], ());
+            }
+            if net.t_out[[t, p]] > 0 {
+                graph.add_edge(nodes[places + t], nodes[p], ());
+            }
+        }
+    }
+    return kosaraju_scc(&graph).len() == 1;
+}
+
+/// A cluster contains a place together with all its output transitions, and a transition together with all its
+/// input places, so the clusters are the connected components of the input arcs.
+fn count_clusters(net: &Net) -> usize {
+    let places = net.place_count();
+    let mut clusters = UnionFind::<usize>::new(places + net.transition_count());
+    for t in 0..net.transition_count() {
+        for p in 0..places {
+            if net.t_in[[t, p]] > 0 {
+                clusters.union(p, places + t);
+            }
+        }
+    }
+    let mut representatives = clusters.into_labeling();
+    representatives.sort_unstable();
+    representatives.dedup();
+    return representatives.len();
+}
+
+/// Computes the rank of the matrix with fraction-free Gaussian elimination.
+fn rank(mut matrix: Vec<Vec<i64>>) -> usize {
+    let columns = matrix.first().map_or(0, |row| row.len());
+    let mut rank = 0;
+    for column in 0..columns {
+        let Some(pivot) = (rank..matrix.len()).find(|&row| matrix[row][column] != 0) else {
+            continue;
+        };
+        matrix.swap(rank, pivot);
+        let pivot_row = matrix[rank].clone();
+        for row in matrix.iter_mut().skip(rank + 1) {
+            let factor = row[column];
+            if factor == 0 {
+                continue;
+            }
+            for (value, &pivot_value) in row.iter_mut().zip(pivot_row.iter()) {
+                *value = *value * pivot_row[column] - pivot_value * factor;
+            }
+            let divisor = row
+                .iter()
+                .fold(0, |divisor, &value| gcd(divisor, value.abs()));
+            if divisor > 1 {
+                row.iter_mut().for_each(|value| *value /= divisor);
+            }
+        }
+        rank += 1;
+    }
+    return rank;
+}
@@ -0,0 +1,122 @@
+use std::collections::HashSet;
+
+use crate::common::*;
+
+/// The places connected to each transition. Read arcs take and return tokens, so they count as both.
+/// Inhibitor and reset arcs are not considered.
+struct Arcs {
+    inputs: Vec<Vec<usize>>,
+    outputs: Vec<Vec<usize>>,
+}
+
+impl Arcs {
+    fn new(net: &Net) -> Self {
+        let places_where = |condition: &dyn Fn(usize, usize) -> bool| -> Vec<Vec<usize>> {
+            (0..net.transition_count())
+                .map(|t| {
+                    (0..net.place_count())
+                        .filter(|&p| condition(t, p))
+                        .collect()
+                })
+                .collect()
+        };
+        Arcs {
+            inputs: places_where(&|t, p| net.t_in[[t, p]] > 0 || net.reads(t, p)),
+            outputs: places_where(&|t, p| net.t_out[[t, p]] > 0 || net.reads(t, p)),
+        }
+    }
+}
+
+/// Enumerates the minimal siphons of the net and checks whether each of them contains an initially marked trap.
+///
+/// A siphon is a set of places that can not gain tokens once it is empty, because every transition that puts tokens
+/// into it also takes tokens from it. A trap is a set of places that can not lose all tokens once it is marked.
+pub(super) fn minimal_siphons(marking: &InputState, net: &Net) -> Vec<Siphon> {
+    let arcs = Arcs::new(net);
+    let places = net.place_count();
+
+    let mut visited: HashSet<Vec<bool>> = HashSet::new();
+    let mut found: Vec<Vec<bool>> = Vec::new();
+    for p in 0..places {
+        let mut start = vec![false; places];
+        start[p] = true;
+        search_siphons(start, &arcs, &mut visited, &mut found);
+    }
+
+    let mut siphons: Vec<Siphon> = found
+        .iter()
+        .filter(|siphon| !found.iter().any(|other| is_strict_subset(other, siphon)))
+        .map(|siphon| {
+            let trap = maximal_trap(siphon, &arcs);
+            let trap_marked = trap
+                .iter()
+                .zip(marking.iter())
+                .any(|(&in_trap, &tokens)| in_trap && tokens > 0);
+            Siphon::new(to_places(siphon), to_places(&trap), trap_marked)
+        })
+        .collect();
+    siphons.sort_by(|a, b| (a.places.len(), &a.places).cmp(&(b.places.len(), &b.places)));
+    siphons.dedup_by(|a, b| a.places == b.places);
+    return siphons;
+}
+
+/// Returns the largest trap of the net, which is the union of all traps.
+pub(super) fn net_maximal_trap(net: &Net) -> Vec<usize> {
+    let arcs = Arcs::new(net);
+    return to_places(&maximal_trap(&vec![true; net.place_count()], &arcs));
+}
+
+/// Extends the set of places until no transition puts tokens into it without taking tokens from it.
+/// Every siphon that contains the set must contain one of the input places of such a transition, so the search
+/// branches over them.
+fn search_siphons(
+    set: Vec<bool>,
+    arcs: &Arcs,
+    visited: &mut HashSet<Vec<bool>>,
+    found: &mut Vec<Vec<bool>>,
+) {
+    if found.iter().any(|siphon| is_subset(siphon, &set)) || !visited.insert(set.clone()) {
+        return;
+    }
+
+    let violating = (0..arcs.inputs.len()).find(|&t| {
+        arcs.outputs[t].iter().any(|&p| set[p]) && !arcs.inputs[t].iter().any(|&p| set[p])
+    });
+    match violating {
+        None => found.push(set),
+        Some(t) => {
+            for &p in arcs.inputs[t].iter() {
+                let mut next = set.clone();
+                next[p] = true;
+                search_siphons(next, arcs, visited, found);
+            }
+        }
+    }
+}
+
+/// Removes places from the set until every transition that takes tokens from it also puts tokens back into it.
+fn maximal_trap(set: &[bool], arcs: &Arcs) -> Vec<bool> {
+    let mut trap = set.to_vec();
+    loop {
+        let leaking = (0..arcs.inputs.len()).find_map(|t| {
+            let refills = arcs.outputs[t].iter().any(|&p| trap[p]);
+            arcs.inputs[t].iter().find(|&&p| trap[p] && !refills)
+        });
+        match leaking {
+            Some(&p) => trap[p] = false,
+            None => return trap,
+        }
+    }
+}
+
+fn is_subset(subset: &[bool], superset: &[bool]) -> bool {
+    subset.iter().zip(superset.iter()).all(|(&a, &b)| !a || b)
+}
+
+fn is_strict_subset(subset: &[bool], superset: &[bool]) -> bool {
+    is_subset(subset, superset) && subset != superset
+}
+
+fn to_places(set: &[bool]) -> Vec<usize> {
+    (0..set.len()).filter(|&p| set[p]).collect()
+}
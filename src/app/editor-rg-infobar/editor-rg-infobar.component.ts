@@ -43,7 +43,9 @@ export class EditorRgInfobarComponent {
     }
 
     updateOnUnbounded(infos: RGResponse) {
-        this.update("∞", "∞", "❌", "", "", "", infos.message ? infos.message : "net is unbounded")
+        // a dead node of the coverability graph is a deadlock, but without one the graph proves nothing
+        const deadlockFree = infos.has_deadlock ? "❌" : "unknown, the coverability graph can not rule out deadlocks"
+        this.update("∞", "∞", "❌", "unknown", "unknown", deadlockFree, infos.message ? infos.message : "net is unbounded")
     }
 
     private update(states: string, edges: string, bounded: string, live: string, reversible: string, deadlockFree: string, message: string) {
@@ -57,9 +59,10 @@ export class EditorRgInfobarComponent {
         this.message = message
     }
 
-    updateOnError(_: any) {
+    updateOnError(error: any) {
         this.rgResult = []
-        this.message = "Error occurred during reachability graph generation."
+        // the backend explains why the graph could not be created, e.g. because it has too many states
+        this.message = typeof error === "string" ? error : "Error occurred during reachability graph generation."
     }
 
     private formatNumber(num: number): string {
@@ -2,16 +2,138 @@ export type SimulationResponse = {
 	marking: number[],
 	firings: number[],
     deadlocked: boolean,
+    steps: number,
+    seed: number,
+    session: number,
+    trace?: FiringTrace,
+    model_time?: number,
+    events?: {time: number, marking: number[]}[],
+    stopped: boolean,
+    zeno: boolean,
+}
+
+export type FiringTrace = {
+    transitions: number[],
+    markings: {step: number, marking: number[]}[],
 }
 
 export type RGResponse = {
 	states: number,
     edges: number,
-	reversible: boolean,
-    liveness: boolean,
+	// reversible, liveness and has_deadlock stay unknown for a coverability graph, which only proves deadlocks
+	reversible?: boolean,
+    liveness?: boolean,
     bounded: number,
 	bounded_vec: number[],
-    has_deadlock: boolean,
+    capacity_reached: boolean[],
+    has_deadlock?: boolean,
     dot_graph: string,
     message: string,
+    unbounded_witness?: UnboundedWitness,
+    deadlocks: Deadlock[],
+    transition_liveness: TransitionLiveness[],
+    home_states: number[][],
+    reversibility_witness?: {marking: number[], firing_sequence: number[]},
+}
+
+export type TransitionLiveness = {
+    level: 0 | 1 | 3 | 4,
+    never_fires_from?: number[],
+}
+
+export type Deadlock = {
+    marking: number[],
+    firing_sequence: number[],
+}
+
+export type UnboundedWitness = {
+    firing_sequence: number[],
+    ancestor_step: number,
+    ancestor: number[],
+    covering: number[],
+    growing_places: number[],
+}
+
+export type StateEquationResponse = {
+    reachable?: boolean,
+    parikh_vector?: number[],
+    firing_sequence?: number[],
+    message: string,
+}
+
+export type ReachabilityQuery =
+    {kind: "marking", marking: number[]} |
+    {kind: "tokens", conditions: TokenCondition[]} |
+    {kind: "expression", expression: string}
+
+export type TokenCondition = {
+    place: number,
+    comparison: "less" | "less_equal" | "equal" | "not_equal" | "greater_equal" | "greater",
+    tokens: number,
+}
+
+export type ReachabilityResponse = {
+    reachable?: boolean,
+    firing_sequence?: number[],
+    markings?: number[][],
+    states: number,
+    message: string,
+}
+
+export type CtlResponse = {
+    holds: boolean,
+    satisfying_states: number[][],
+    states: number,
+    message: string,
+}
+
+export type LassoStep = {
+    marking: number[],
+    transition: number | null,
+}
+
+export type LtlResponse = {
+    holds: boolean,
+    counterexample: {stem: LassoStep[], cycle: LassoStep[]} | null,
+    states: number,
+    message: string,
+}
+
+export type StructuralResponse = {
+    // the token sum is constant in all reachable markings, but only an upper bound in nets with reset arcs
+    p_invariants: {weights: number[], token_sum: number}[],
+    place_bounds: (number | null)[],
+    t_invariants: number[][],
+    consistent: boolean,
+    repetitive: boolean,
+    siphons: {places: number[], maximal_trap: number[], has_marked_trap: boolean, can_be_emptied?: boolean}[],
+    siphons_contain_marked_traps: boolean,
+    maximal_trap: number[],
+    classification: {
+        ordinary: ClassMembership,
+        state_machine: ClassMembership,
+        marked_graph: ClassMembership,
+        free_choice: ClassMembership,
+        extended_free_choice: ClassMembership,
+        asymmetric_choice: ClassMembership,
+    },
+    free_choice?: FreeChoiceAnalysis,
+}
+
+export type FreeChoiceAnalysis = {
+    strongly_connected: boolean,
+    consistent: boolean,
+    conservative: boolean,
+    rank: number,
+    clusters: number,
+    rank_condition: boolean,
+    well_formed: boolean,
+    live?: boolean,
+    bounded?: boolean,
+}
+
+export type ClassMembership = {
+    member: boolean,
+    offending_places: number[],
+    offending_transitions: number[],
 }
@@ -10,14 +10,15 @@ export class ReachabilityGraphService {
     async createRG(marking: number[], pxt_in: number[][], pxt_out: number[][]) {
         try {
             const start = performance.now();
-            const data = await invoke<RGResponse>('check_properties', {marking: marking, transitionInputs: pxt_in, transitionOutputs: pxt_out});
+            // placeNames is left out, the editor names places p1, p2, ... which formulas would read as indices
+            const data = await invoke<RGResponse>('check_properties', {marking: marking, net: {transitionInputs: pxt_in, transitionOutputs: pxt_out}});
             const end = performance.now();
             const total = end - start;
             console.log(`Generating ${data} took ${total}ms`)
             return data
         } catch (error) {
             console.error('Error calling the simulator:', error);
-            return Promise.reject();
+            return Promise.reject(error);
         }
     }
 }
@@ -21,7 +21,8 @@ type SimulationEvent = {
  * Service that handles the communication with the actual simulator backend.
  * Contains an internal state machine that represents the state of the simulator and handles all simulation requests,
  * including invalid calls (e.g. continuing a non-existing simulation).
- * Only a single simulation may run at a time.
+ * Only a single simulation may run at a time per service. The backend session of this service is reused for every
+ * new simulation, so that simulations of other windows are not affected.
  * If a different / new simulation should be started, the previous simulation must be stopped or paused first.
  */
 @Injectable({
@@ -34,6 +35,7 @@ export class SimulatorService {
 	private currentState: States = States.Stopped;
     private currentUpdateTime: number = 10000
 	private startState: number[] = []
+	private session: number | undefined = undefined
 
     async step(state: number[], in_matrix: number[][], out_matrix: number[][]) {
 		if (this.currentState == States.Running) {
@@ -47,6 +49,7 @@ export class SimulatorService {
 
 		this.currentState = States.Running
 		this.invokeSimulationStep(state, in_matrix, out_matrix).then(result => {
+			this.session = result.session
             this.emitResult(result, States.Paused)
 			this.currentState = States.Paused
 		})
@@ -62,7 +65,8 @@ export class SimulatorService {
 		this.currentUpdateTime = updateTime
 		this.startState = state
 
-		this.invokeSimulationStart(state, in_matrix, out_matrix, updateTime).then(() => {
+		this.invokeSimulationStart(state, in_matrix, out_matrix, updateTime).then(result => {
+			this.session = result.session
 			this.continueInternal(updateTime);
 		}).catch(e => this.handleError(e))
 	}
@@ -94,8 +98,8 @@ export class SimulatorService {
 
 	private async continueInternal(updateTime: number) {
         const result = await this.invokeSimulationContinue(updateTime)
-        // if we know that the simulation is deadlock, we request a pause
-		let nextState = result.deadlocked ? States.PauseRequested : this.currentState
+        // if we know that the simulation is deadlock or cannot advance its model time, we request a pause
+		let nextState = result.deadlocked || result.zeno ? States.PauseRequested : this.currentState
 
         this.emitResult(result, nextState)
 		if (nextState == States.StopRequested) {
@@ -113,9 +117,9 @@ export class SimulatorService {
 	private async invokeSimulationStart(vector: number[], in_matrix: number[][], out_matrix: number[][], updateTime: number): Promise<SimulationResponse> {
         try {
             return await invoke<SimulationResponse>('simulate_start', {
+                session: this.session,
                 marking: vector,
-                transitionInputs: in_matrix,
-                transitionOutputs: out_matrix,
+                net: {transitionInputs: in_matrix, transitionOutputs: out_matrix},
                 updateTime: updateTime
             });
         } catch (error) {
@@ -127,9 +131,9 @@ export class SimulatorService {
 	private async invokeSimulationStep(vector: number[], in_matrix: number[][], out_matrix: number[][]): Promise<SimulationResponse> {
         try {
             return await invoke<SimulationResponse>('simulate_start_step', {
+                session: this.session,
                 marking: vector,
-                transitionInputs: in_matrix,
-                transitionOutputs: out_matrix,
+                net: {transitionInputs: in_matrix, transitionOutputs: out_matrix},
             });
         } catch (error) {
             this.handleError(error)
@@ -139,7 +143,7 @@ export class SimulatorService {
 
     private async invokeSimulationContinue(updateTime: number): Promise<SimulationResponse> {
         try {
-            return await invoke<SimulationResponse>('simulate_continue', {updateTime: updateTime});
+            return await invoke<SimulationResponse>('simulate_continue', {session: this.session, updateTime: updateTime});
         } catch (error) {
             this.handleError(error)
             return Promise.reject()